@@ -0,0 +1,479 @@
+// Matches between engine configurations, to tell which of them plays better.
+// The two games of every pair are played with the same seed,
+// each engine moving first in one of them
+use game::{Evaluator, GameState};
+use mcts_hashtable::MctsTable;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use std::f64;
+use std::fmt;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+// Standard normal quantile of the 95% confidence intervals
+const Z95: f64 = 1.96;
+// Half a win and half a loss added to the results of the test,
+// so it doesn't decide after a few games that all ended the same way
+const PRIOR: f64 = 0.5;
+
+#[derive(Clone)]
+pub struct EngineConfig<G: GameState> {
+    pub name: String,
+    // Playouts from scratch before every move
+    pub playouts: u32,
+    pub max_its: u32,
+    pub exploration: f64,
+    pub evaluator: Option<Arc<Evaluator<G>>>,
+}
+
+impl<G: GameState> EngineConfig<G> {
+    pub fn new(name: &str, playouts: u32) -> Self {
+        EngineConfig {
+            name: name.to_string(),
+            playouts: playouts,
+            max_its: 400,
+            exploration: f64::consts::SQRT_2,
+            evaluator: None,
+        }
+    }
+
+    fn table(&self, s: &G, seed: [u32; 4]) -> MctsTable<G> {
+        let mut table = match self.evaluator {
+            Some(ref e) => MctsTable::with_evaluator(s.clone(), e.clone()),
+            None => MctsTable::with_state(s.clone()),
+        };
+        table.seed(seed);
+        table.set_exploration(self.exploration);
+        table
+    }
+
+    fn choose(&self, s: &G, seed: [u32; 4]) -> G::Move {
+        let mut table = self.table(s, seed);
+        for _ in 0..self.playouts {
+            table.playout(s, self.max_its);
+        }
+        table.best_choice(s).expect("Unfinished games have legal moves")
+    }
+}
+
+// Moves of players without a seat, such as rolls of the dice
+pub trait Chance<G: GameState>: Sync + Send {
+    fn chance_move(&self, s: &G, rng: &mut XorShiftRng) -> G::Move;
+}
+
+// Every legal move is as likely
+pub struct Uniform;
+
+impl<G: GameState> Chance<G> for Uniform {
+    fn chance_move(&self, s: &G, rng: &mut XorShiftRng) -> G::Move {
+        let moves = s.legal_moves();
+        rng.choose(&moves).cloned().expect("Unfinished games have legal moves")
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Format {
+    RoundRobin,
+    // The first engine against each of the others
+    Gauntlet,
+}
+
+// Sequential probability ratio test of whether the first engine of a pairing
+// is `elo0` or `elo1` stronger than the second,
+// with the chances of wrongly accepting either
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Verdict {
+    H0,
+    H1,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Sprt {
+            elo0: elo0,
+            elo1: elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    // The log likelihood ratios at which H0 and H1 are accepted
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    // Normal approximation of the log likelihood ratio of H1 against H0
+    pub fn llr(&self, p: &Pairing) -> f64 {
+        if p.games == 0 {
+            return 0.0;
+        }
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        let (wins, losses) = (p.wins as f64 + PRIOR, p.losses as f64 + PRIOR);
+        let variance = variance(wins, p.draws as f64, losses);
+        p.games as f64 * (s1 - s0) * (2.0 * p.score() - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, p: &Pairing) -> Option<Verdict> {
+        let llr = self.llr(p);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Some(Verdict::H0)
+        } else if llr >= upper {
+            Some(Verdict::H1)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct ArenaSettings {
+    pub format: Format,
+    // Games per pairing, rounded up to an even number so both engines move first as often
+    pub games: usize,
+    pub threads: usize,
+    pub seed: u32,
+    // Stop pairings as soon as the test decides
+    pub sprt: Option<Sprt>,
+}
+
+impl Default for ArenaSettings {
+    fn default() -> Self {
+        ArenaSettings {
+            format: Format::RoundRobin,
+            games: 100,
+            threads: 1,
+            seed: 1,
+            sprt: None,
+        }
+    }
+}
+
+// The games between two engines, counted for the first of them
+#[derive(PartialEq, Clone, Debug)]
+pub struct Pairing {
+    pub engines: (usize, usize),
+    pub games: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    // Total scores of the first and second engine
+    pub points: f64,
+    pub opponent_points: f64,
+    pub verdict: Option<Verdict>,
+}
+
+// An estimate with its 95% confidence interval
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Interval {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Pairing {
+    fn new(engines: (usize, usize)) -> Self {
+        Pairing {
+            engines: engines,
+            games: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            points: 0.0,
+            opponent_points: 0.0,
+            verdict: None,
+        }
+    }
+
+    fn add(&mut self, points: f64, opponent_points: f64) {
+        self.games += 1;
+        self.points += points;
+        self.opponent_points += opponent_points;
+        if points > opponent_points {
+            self.wins += 1;
+        } else if points < opponent_points {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    // Wins count one and draws a half
+    pub fn score(&self) -> f64 {
+        if self.games == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games as f64
+    }
+
+    // Of the score of a single game
+    pub fn variance(&self) -> f64 {
+        variance(self.wins as f64, self.draws as f64, self.losses as f64)
+    }
+
+    pub fn points_per_game(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.points / self.games as f64
+        }
+    }
+
+    // Elo difference of the first engine over the second, infinite after only wins or losses
+    pub fn elo(&self) -> Interval {
+        let s = self.score();
+        let margin = Z95 * (self.variance() / self.games.max(1) as f64).sqrt();
+        Interval {
+            estimate: elo(s),
+            lower: elo(s - margin),
+            upper: elo(s + margin),
+        }
+    }
+
+    fn reversed(&self) -> Self {
+        Pairing {
+            engines: (self.engines.1, self.engines.0),
+            games: self.games,
+            wins: self.losses,
+            losses: self.wins,
+            draws: self.draws,
+            points: self.opponent_points,
+            opponent_points: self.points,
+            verdict: self.verdict,
+        }
+    }
+
+    fn merge(&mut self, other: &Pairing) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+        self.points += other.points;
+        self.opponent_points += other.opponent_points;
+    }
+}
+
+fn variance(wins: f64, draws: f64, losses: f64) -> f64 {
+    let games = wins + draws + losses;
+    if games == 0.0 {
+        return 0.0;
+    }
+    let s = (wins + 0.5 * draws) / games;
+    let square = |x: f64| x * x;
+    (wins * square(1.0 - s) + draws * square(0.5 - s) + losses * square(s)) / games
+}
+
+fn elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        f64::INFINITY
+    } else {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Report {
+    pub names: Vec<String>,
+    pub pairings: Vec<Pairing>,
+}
+
+impl Report {
+    // Every game of the engine counted for it, with the engine as its own opponent
+    pub fn standing(&self, engine: usize) -> Pairing {
+        let mut total = Pairing::new((engine, engine));
+        for p in &self.pairings {
+            if p.engines.0 == engine {
+                total.merge(p);
+            } else if p.engines.1 == engine {
+                total.merge(&p.reversed());
+            }
+        }
+        total
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for p in &self.pairings {
+            let elo = p.elo();
+            write!(
+                f,
+                "{} vs {}: {} games, +{} -{} ={}, score {:.1}%, {:+.3} points per game, Elo {:+.0} [{:+.0}, {:+.0}]",
+                self.names[p.engines.0],
+                self.names[p.engines.1],
+                p.games,
+                p.wins,
+                p.losses,
+                p.draws,
+                100.0 * p.score(),
+                p.points_per_game(),
+                elo.estimate,
+                elo.lower,
+                elo.upper
+            )?;
+            match p.verdict {
+                Some(v) => writeln!(f, ", SPRT accepted {:?}", v)?,
+                None => writeln!(f)?,
+            }
+        }
+        let mut standings: Vec<_> = (0..self.names.len()).map(|i| self.standing(i)).collect();
+        standings.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap());
+        for s in &standings {
+            writeln!(
+                f,
+                "{}: {} games, +{} -{} ={}, score {:.1}%, {:+.3} points per game",
+                self.names[s.engines.0],
+                s.games,
+                s.wins,
+                s.losses,
+                s.draws,
+                100.0 * s.score(),
+                s.points_per_game()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Arena<G: GameState> {
+    pub start: G,
+    // The players the engines take turns being, the one moving first first
+    pub seats: (G::Player, G::Player),
+    pub chance: Arc<Chance<G>>,
+    pub engines: Vec<EngineConfig<G>>,
+    pub settings: ArenaSettings,
+}
+
+impl<G: GameState + 'static> Arena<G> {
+    pub fn new(seats: (G::Player, G::Player), chance: Arc<Chance<G>>, engines: Vec<EngineConfig<G>>) -> Self {
+        Arena {
+            start: G::new(),
+            seats: seats,
+            chance: chance,
+            engines: engines,
+            settings: ArenaSettings::default(),
+        }
+    }
+
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let n = self.engines.len();
+        match self.settings.format {
+            Format::RoundRobin => (0..n).flat_map(|a| ((a + 1)..n).map(move |b| (a, b))).collect(),
+            Format::Gauntlet => (1..n).map(|b| (0, b)).collect(),
+        }
+    }
+
+    // Plays every pairing on `threads` threads, calling `progress` after every game
+    pub fn run<F: FnMut(&Report)>(&self, mut progress: F) -> Report {
+        let pairings = self.pairings();
+        let games = (self.settings.games + 1) / 2 * 2;
+        // Alternating pairings so early stops are spread out
+        let jobs: Vec<(usize, usize)> = (0..games)
+            .flat_map(|g| (0..pairings.len()).map(move |p| (p, g)))
+            .collect();
+        let jobs = Arc::new(Mutex::new(jobs.into_iter()));
+        let stopped: Arc<Vec<AtomicBool>> = Arc::new(pairings.iter().map(|_| AtomicBool::new(false)).collect());
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..self.settings.threads.max(1) {
+            let jobs = jobs.clone();
+            let stopped = stopped.clone();
+            let tx = tx.clone();
+            let pairings = pairings.clone();
+            let engines = self.engines.clone();
+            let start = self.start.clone();
+            let seats = self.seats.clone();
+            let chance = self.chance.clone();
+            let seed = self.settings.seed;
+            thread::spawn(move || loop {
+                let job = jobs.lock().unwrap().next();
+                let (p, g) = match job {
+                    Some(job) => job,
+                    None => break,
+                };
+                if stopped[p].load(Ordering::SeqCst) {
+                    continue;
+                }
+                let (a, b) = pairings[p];
+                // Even games have the first engine of the pairing moving first
+                let (first, second) = if g % 2 == 0 { (a, b) } else { (b, a) };
+                let game = Game {
+                    start: &start,
+                    seats: &seats,
+                    chance: &*chance,
+                    seed: [seed, p as u32, g as u32 / 2, 0x9e37_79b9],
+                };
+                let (x, y) = game.play(&engines[first], &engines[second], g as u32);
+                let result = if g % 2 == 0 { (x, y) } else { (y, x) };
+                if tx.send((p, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+        let mut report = Report {
+            names: self.engines.iter().map(|e| e.name.clone()).collect(),
+            pairings: pairings.iter().map(|&engines| Pairing::new(engines)).collect(),
+        };
+        for (p, (x, y)) in rx {
+            {
+                let pairing = &mut report.pairings[p];
+                pairing.add(x, y);
+                if pairing.verdict.is_none() {
+                    pairing.verdict = self.settings.sprt.and_then(|sprt| sprt.verdict(pairing));
+                    if pairing.verdict.is_some() {
+                        stopped[p].store(true, Ordering::SeqCst);
+                    }
+                }
+            }
+            progress(&report);
+        }
+        report
+    }
+}
+
+struct Game<'a, G: GameState + 'a> {
+    start: &'a G,
+    seats: &'a (G::Player, G::Player),
+    chance: &'a Chance<G>,
+    // Both games of a pair get the same seed
+    seed: [u32; 4],
+}
+
+impl<'a, G: GameState> Game<'a, G> {
+    // Scores of the engines moving first and second
+    fn play(&self, first: &EngineConfig<G>, second: &EngineConfig<G>, game: u32) -> (f64, f64) {
+        let mut rng = XorShiftRng::from_seed(self.seed);
+        let mut s = self.start.clone();
+        let mut ply = 0;
+        loop {
+            if let Some(scores) = s.scores() {
+                return (scores[&self.seats.0], scores[&self.seats.1]);
+            }
+            let p = s.current_player();
+            let seed = [self.seed[0], self.seed[1] ^ ply << 16, game, 0x7f4a_7c15];
+            let m = if p == self.seats.0 {
+                first.choose(&s, seed)
+            } else if p == self.seats.1 {
+                second.choose(&s, seed)
+            } else {
+                self.chance.chance_move(&s, &mut rng)
+            };
+            s.apply(m);
+            ply += 1;
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// An engine searching in the background, on its own time and on its opponents'.
+// It follows the game from move to move, keeping what it learnt about the positions still reachable
+use game::{Evaluator, GameState};
+use mcts_hashtable::MctsTable;
+use rand;
+use std::f64;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Playouts between checks for a new position
+const BATCH: u32 = 32;
+// Longest wait in milliseconds between checks whether to stop searching for a move
+const WAIT: u64 = 100;
+
+#[derive(Clone)]
+pub struct EngineSettings<G: GameState> {
+    pub threads: usize,
+    pub max_its: u32,
+    pub exploration: f64,
+    // Searching pauses once the table has this many states, until the position changes
+    pub max_nodes: usize,
+    pub evaluator: Option<Arc<Evaluator<G>>>,
+}
+
+impl<G: GameState> Default for EngineSettings<G> {
+    fn default() -> Self {
+        EngineSettings {
+            threads: 1,
+            max_its: 400,
+            exploration: f64::consts::SQRT_2,
+            // This constant depends on game state size
+            // and was chosen to remain at a reasonable level of memory use
+            max_nodes: 1 << 20,
+            evaluator: None,
+        }
+    }
+}
+
+// How long to think about a move, whichever runs out first.
+// Playouts are counted for the position, including those made while pondering
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct Budget {
+    pub playouts: Option<u32>,
+    pub time: Option<Duration>,
+}
+
+impl Budget {
+    pub fn spent(&self, playouts: u32, elapsed: Duration) -> bool {
+        self.playouts.map_or(false, |p| playouts >= p) || self.time.map_or(false, |t| elapsed >= t)
+    }
+}
+
+struct Control<G> {
+    root: G,
+    searching: bool,
+    full: bool,
+    quit: bool,
+}
+
+struct Shared<G: GameState> {
+    table: Mutex<MctsTable<G>>,
+    control: Mutex<Control<G>>,
+    // Signalled when there's something new to search, or when it's time to quit
+    changed: Condvar,
+    // Signalled after every batch of playouts
+    progress: Condvar,
+    max_its: u32,
+    max_nodes: usize,
+}
+
+pub struct Engine<G: GameState> {
+    shared: Arc<Shared<G>>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl<G: GameState + 'static> Engine<G> {
+    // The engine is idle until asked to ponder or to move
+    pub fn new(s: G, settings: EngineSettings<G>) -> Self {
+        let mut table = match settings.evaluator {
+            Some(ref e) => MctsTable::with_evaluator(s.clone(), e.clone()),
+            None => MctsTable::with_state(s.clone()),
+        };
+        table.set_exploration(settings.exploration);
+        let shared = Arc::new(Shared {
+            table: Mutex::new(table),
+            control: Mutex::new(Control {
+                root: s,
+                searching: false,
+                full: false,
+                quit: false,
+            }),
+            changed: Condvar::new(),
+            progress: Condvar::new(),
+            max_its: settings.max_its,
+            max_nodes: settings.max_nodes,
+        });
+        let workers = (0..settings.threads.max(1))
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || work(&shared))
+            })
+            .collect();
+        Engine {
+            shared: shared,
+            workers: workers,
+        }
+    }
+
+    // Keeps searching the position until stopped
+    pub fn ponder(&self, s: G) {
+        let mut control = self.shared.control.lock().unwrap();
+        if control.root != s {
+            self.reroot(&mut control, s);
+        }
+        control.searching = true;
+        self.shared.changed.notify_all();
+    }
+
+    // Workers finish the playouts they are in the middle of
+    pub fn stop(&self) {
+        self.shared.control.lock().unwrap().searching = false;
+    }
+
+    // Follows a move by either side, forgetting positions no longer reachable
+    pub fn play(&self, m: G::Move) {
+        let mut control = self.shared.control.lock().unwrap();
+        let mut s = control.root.clone();
+        s.apply(m);
+        self.reroot(&mut control, s);
+        self.shared.changed.notify_all();
+    }
+
+    // Plays the move and thinks about the position it leads to
+    pub fn opponent_moved(&self, m: G::Move) {
+        self.play(m);
+        let root = self.position();
+        self.ponder(root);
+    }
+
+    // Searches the current position until the budget is spent
+    // and there is at least one playout to go on, searching on afterwards
+    pub fn best_move(&self, budget: Budget) -> Option<G::Move> {
+        self.best_move_until(budget, |_| false)
+    }
+
+    // As `best_move`, also stopping once `stop` is true.
+    // It is given the playouts so far after every batch of them, or every so often without any
+    pub fn best_move_until<F: FnMut(u32) -> bool>(&self, budget: Budget, mut stop: F) -> Option<G::Move> {
+        let start = Instant::now();
+        let root = self.position();
+        if root.finished() {
+            return None;
+        }
+        self.ponder(root.clone());
+        let mut control = self.shared.control.lock().unwrap();
+        loop {
+            let playouts = self.playouts(&root);
+            // Nothing more will be learnt once the table is full
+            if (playouts > 0 && budget.spent(playouts, start.elapsed())) || (control.full && playouts > 0) {
+                break;
+            }
+            if stop(playouts) {
+                break;
+            }
+            let wait = budget
+                .time
+                .and_then(|t| t.checked_sub(start.elapsed()))
+                .map_or(Duration::from_millis(WAIT), |left| left.min(Duration::from_millis(WAIT)));
+            control = self.shared.progress.wait_timeout(control, wait).unwrap().0;
+        }
+        drop(control);
+        self.table().best_choice(&root)
+    }
+
+    pub fn position(&self) -> G {
+        self.shared.control.lock().unwrap().root.clone()
+    }
+
+    pub fn playouts(&self, s: &G) -> u32 {
+        self.table().nodes.get(s).map_or(0, |meta| meta.playouts)
+    }
+
+    // Blocks the workers while held
+    pub fn table(&self) -> MutexGuard<'_, MctsTable<G>> {
+        self.shared.table.lock().unwrap()
+    }
+
+    fn reroot(&self, control: &mut Control<G>, new: G) {
+        {
+            let mut table = self.table();
+            // With no room left and nothing known about the new position, start afresh
+            if !table.nodes.contains_key(&new) && table.nodes.len() > self.shared.max_nodes {
+                table.nodes.clear();
+            }
+            let old_meta = table.nodes.get(&control.root).cloned();
+            // Positions reached otherwise, such as by taking moves back, may be revisited,
+            // so what was learnt about them is kept
+            let followed = old_meta
+                .as_ref()
+                .map_or(false, |meta| meta.moves.values().any(|&(ref s, _)| *s == new));
+            if let (Some(old_meta), true) = (old_meta, followed) {
+                for (_, (s, _)) in old_meta.moves {
+                    if s != new {
+                        table.garbage_collect(&s);
+                    }
+                }
+            }
+        }
+        control.root = new;
+        control.full = false;
+    }
+}
+
+impl<G: GameState> Drop for Engine<G> {
+    fn drop(&mut self) {
+        self.shared.control.lock().unwrap().quit = true;
+        self.shared.changed.notify_all();
+        for worker in self.workers.drain(..) {
+            worker.join().expect("Engine worker panicked");
+        }
+    }
+}
+
+// The table is only locked to descend it and to back playouts up,
+// for the workers to play games out and evaluate positions side by side
+fn work<G: GameState>(shared: &Shared<G>) {
+    let evaluator = shared.table.lock().unwrap().evaluator();
+    let mut rng = rand::weak_rng();
+    loop {
+        let s = {
+            let mut control = shared.control.lock().unwrap();
+            while !control.quit && !(control.searching && !control.full) {
+                control = shared.changed.wait(control).unwrap();
+            }
+            if control.quit {
+                return;
+            }
+            control.root.clone()
+        };
+        let mut full = false;
+        for _ in 0..BATCH {
+            let mut descent = shared.table.lock().unwrap().descend(&s, shared.max_its);
+            descent.play_out(evaluator.as_deref(), &mut rng);
+            let mut table = shared.table.lock().unwrap();
+            table.back_up(descent);
+            full = table.nodes.len() > shared.max_nodes;
+        }
+        {
+            let mut control = shared.control.lock().unwrap();
+            if full && control.root == s {
+                control.full = true;
+            }
+        }
+        shared.progress.notify_all();
+    }
+}
@@ -0,0 +1,154 @@
+// Pip counts and other measures of a position that evaluators and players use
+use game::GameState;
+use super::{board, travelled, Backgammon, Point};
+use super::Location::{self, Bar, Board};
+use super::bearoff::OneSided;
+use std::sync::OnceLock;
+
+// Average number of pips a roll moves, doubles counting twice
+const PIPS_PER_ROLL: f64 = 49.0 / 6.0;
+
+// Generating the database takes a moment, so it's done once when first needed
+fn one_sided() -> &'static OneSided {
+    static ONE_SIDED: OnceLock<OneSided> = OnceLock::new();
+    ONE_SIDED.get_or_init(|| OneSided::generate(15))
+}
+
+// The kind of game being played, which decides what matters in a position
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Class {
+    Over,
+    // No contact and every piece in its home board
+    Bearoff,
+    // No contact
+    Race,
+    // Contact without any of the structures below
+    Contact,
+    // A side keeps an anchor on the opponent's four, five, six or bar point
+    Holding,
+    // A side has five or more points in a row with opposing pieces behind them
+    Prime,
+    // A side trailing in the race keeps two or more anchors in the opponent's home board
+    BackGame,
+}
+
+impl Backgammon {
+    // Pips the player needs to bear off every piece
+    pub fn pip_count(&self, p: bool) -> u32 {
+        locations()
+            .into_iter()
+            .map(|l| self.pieces(l, p) as u32 * (25 - travelled(l, p)) as u32)
+            .sum()
+    }
+
+    // Pip counts of (white, black)
+    pub fn pip_counts(&self) -> (u32, u32) {
+        (self.pip_count(true), self.pip_count(false))
+    }
+
+    // Whether any pieces still have to get past each other
+    pub fn is_contact(&self) -> bool {
+        // Positions along white's way, from white's bar to black's bar
+        let on_way = |l: Location, p: bool| if p { travelled(l, p) } else { 25 - travelled(l, p) };
+        let on_way_of = |p: bool| {
+            locations()
+                .into_iter()
+                .filter(|&l| self.pieces(l, p) > 0)
+                .map(|l| on_way(l, p))
+                .collect::<Vec<_>>()
+        };
+        match (on_way_of(true).into_iter().min(), on_way_of(false).into_iter().max()) {
+            (Some(w), Some(b)) => w < b,
+            _ => false,
+        }
+    }
+
+    pub fn is_race(&self) -> bool {
+        !self.finished() && !self.is_contact()
+    }
+
+    pub fn classify(&self) -> Class {
+        if self.finished() {
+            Class::Over
+        } else if !self.is_contact() {
+            if self.all_homeboard(true) && self.all_homeboard(false) {
+                Class::Bearoff
+            } else {
+                Class::Race
+            }
+        } else if self.back_game(true) || self.back_game(false) {
+            Class::BackGame
+        } else if self.primes(true) || self.primes(false) {
+            Class::Prime
+        } else if self.anchors(true, 4, 7) >= 1 || self.anchors(false, 4, 7) >= 1 {
+            Class::Holding
+        } else {
+            Class::Contact
+        }
+    }
+
+    // Pips the player needs counting the pips rolls typically waste,
+    // that is the expected number of rolls to bear off times the pips in an average roll.
+    // Exact for pieces in the home board, pieces outside it are counted
+    // as if they reached the six point without waste
+    pub fn effective_pip_count(&self, p: bool) -> f64 {
+        let mut home = [0; 6];
+        let mut outside = 0;
+        for l in locations() {
+            let n = self.pieces(l, p);
+            let distance = 25 - travelled(l, p);
+            if distance <= 6 {
+                home[distance as usize - 1] += n;
+            } else {
+                home[5] += n;
+                outside += n as u32 * (distance as u32 - 6);
+            }
+        }
+        match one_sided().expected_rolls(home) {
+            Some(rolls) => outside as f64 + rolls * PIPS_PER_ROLL,
+            // Only made up positions have more than 15 pieces
+            None => self.pip_count(p) as f64,
+        }
+    }
+
+    // Points made by the player between `from` and `to` pips travelled
+    fn anchors(&self, p: bool, from: u8, to: u8) -> usize {
+        board()
+            .into_iter()
+            .filter(|&l| travelled(l, p) >= from && travelled(l, p) <= to && self.pieces(l, p) >= 2)
+            .count()
+    }
+
+    fn back_game(&self, p: bool) -> bool {
+        self.anchors(p, 1, 6) >= 2 && self.pip_count(p) > self.pip_count(!p)
+    }
+
+    // Whether the player has five or more points in a row with opposing pieces behind them
+    fn primes(&self, p: bool) -> bool {
+        let made = |t: u8| self.pieces(own_point(t, p), p) >= 2;
+        // Where the opponent's rearmost piece is, in the player's own terms
+        let trapped = locations()
+            .into_iter()
+            .filter(|&l| self.pieces(l, !p) > 0)
+            .map(|l| 25 - travelled(l, !p))
+            .max();
+        let mut run = 0;
+        for t in 1..25 {
+            run = if made(t) { run + 1 } else { 0 };
+            if run >= 5 && trapped.map_or(false, |rearmost| rearmost > t) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn locations() -> Vec<Location> {
+    let mut v = board();
+    v.push(Bar);
+    v
+}
+
+fn own_point(t: u8, p: bool) -> Location {
+    Board(Point(if p { t } else { 25 - t }))
+}
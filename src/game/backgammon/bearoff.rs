@@ -0,0 +1,345 @@
+// Exact bearoff databases, solved backwards from positions with fewer pips.
+// A side's position is the number of pieces on each of its home points,
+// `home[i]` being the pieces i + 1 pips away from being borne off
+use game::{Evaluator, GameState, ScoreBoard};
+use super::{travelled, Backgammon, Phase, Point};
+use super::Location::Board;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub type Home = [u8; 6];
+
+const MAGIC: &'static [u8] = b"BOFF";
+const ONE_SIDED: u8 = 1;
+const TWO_SIDED: u8 = 2;
+// Expected rolls are stored in fixed point, with at most 15 pieces they stay below 16
+const ROLLS_SCALE: f64 = 4096.0;
+const CHANCE_SCALE: f64 = 65535.0;
+
+// Expected number of rolls to bear off every position
+// with up to `checkers` pieces, ignoring the opponent
+#[derive(PartialEq, Clone, Debug)]
+pub struct OneSided {
+    checkers: u8,
+    rolls: Vec<u16>,
+}
+
+// Chance of winning for the side on roll, for every pair of positions
+// with up to `checkers` pieces each.
+// With fewer than 15 pieces left nobody can be gammoned, so this is all there is to know
+#[derive(PartialEq, Clone, Debug)]
+pub struct TwoSided {
+    checkers: u8,
+    wins: Vec<u16>,
+}
+
+impl OneSided {
+    pub fn generate(checkers: u8) -> Self {
+        assert!(checkers <= 15, "Each side only has 15 pieces");
+        let positions = positions(checkers);
+        let mut rolls = vec![0.0; positions.len()];
+        for &i in &by_pips(&positions) {
+            let home = positions[i];
+            if home == [0; 6] {
+                continue;
+            }
+            rolls[i] = 1.0 +
+                rolls_and_chances()
+                    .into_iter()
+                    .map(|(roll, chance)| {
+                        let best = roll_plays(home, roll)
+                            .into_iter()
+                            .map(|r| rolls[rank(r, checkers)])
+                            .fold(::std::f64::INFINITY, f64::min);
+                        chance * best
+                    })
+                    .sum::<f64>();
+        }
+        OneSided {
+            checkers: checkers,
+            rolls: rolls.into_iter().map(|r| (r * ROLLS_SCALE).round() as u16).collect(),
+        }
+    }
+
+    pub fn checkers(&self) -> u8 {
+        self.checkers
+    }
+
+    // None if the position has too many pieces for the database
+    pub fn expected_rolls(&self, home: Home) -> Option<f64> {
+        if pieces(home) > self.checkers {
+            return None;
+        }
+        Some(self.rolls[rank(home, self.checkers)] as f64 / ROLLS_SCALE)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let (checkers, rolls) = load(path, ONE_SIDED, |checkers| positions_count(checkers))?;
+        Ok(OneSided {
+            checkers: checkers,
+            rolls: rolls,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        save(path, ONE_SIDED, self.checkers, &self.rolls)
+    }
+}
+
+impl TwoSided {
+    pub fn generate(checkers: u8) -> Self {
+        assert!(checkers < 15, "Gammons aren't accounted for");
+        let positions = positions(checkers);
+        let n = positions.len();
+        // The results of every roll from every position, they are needed over and over
+        let results: Vec<Vec<(f64, Vec<usize>)>> = positions
+            .iter()
+            .map(|&home| {
+                rolls_and_chances()
+                    .into_iter()
+                    .map(|(roll, chance)| {
+                        let ranks = roll_plays(home, roll).into_iter().map(|r| rank(r, checkers)).collect();
+                        (chance, ranks)
+                    })
+                    .collect()
+            })
+            .collect();
+        let pips: Vec<u32> = positions.iter().map(|&home| pip_count(home)).collect();
+        let mut pairs: Vec<(usize, usize)> = (1..n).flat_map(|a| (1..n).map(move |b| (a, b))).collect();
+        pairs.sort_by_key(|&(a, b)| pips[a] + pips[b]);
+        let mut wins = vec![0.0; n * n];
+        for (a, b) in pairs {
+            wins[a * n + b] = results[a]
+                .iter()
+                .map(|&(chance, ref ranks)| {
+                    let best = ranks
+                        .iter()
+                        // Bearing off the last piece wins, the opponent is on roll otherwise
+                        .map(|&r| if r == 0 { 1.0 } else { 1.0 - wins[b * n + r] })
+                        .fold(0.0, f64::max);
+                    chance * best
+                })
+                .sum();
+        }
+        TwoSided {
+            checkers: checkers,
+            wins: wins.into_iter().map(|w| (w * CHANCE_SCALE).round() as u16).collect(),
+        }
+    }
+
+    pub fn checkers(&self) -> u8 {
+        self.checkers
+    }
+
+    // Chance of winning for the side about to roll,
+    // None if either side has too many pieces or no pieces left
+    pub fn win_chance(&self, on_roll: Home, other: Home) -> Option<f64> {
+        let (a, b) = (pieces(on_roll), pieces(other));
+        if a > self.checkers || b > self.checkers || a == 0 || b == 0 {
+            return None;
+        }
+        let n = positions_count(self.checkers);
+        let i = rank(on_roll, self.checkers) * n + rank(other, self.checkers);
+        Some(self.wins[i] as f64 / CHANCE_SCALE)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let (checkers, wins) = load(path, TWO_SIDED, |checkers| positions_count(checkers).pow(2))?;
+        Ok(TwoSided {
+            checkers: checkers,
+            wins: wins,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        save(path, TWO_SIDED, self.checkers, &self.wins)
+    }
+}
+
+// Exact expected scores for bearoffs in the database, before the dice are rolled.
+// The cube is treated as dead
+impl Evaluator<Backgammon> for TwoSided {
+    fn evaluate(&self, s: &Backgammon) -> Option<ScoreBoard<Backgammon>> {
+        if s.finished() || !(s.phase == Phase::Cube || s.phase == Phase::Roll) {
+            return None;
+        }
+        let p = s.player;
+        let win = self.win_chance(home_board(s, p)?, home_board(s, !p)?)?;
+        let cube = s.cube.map_or(1, |c| c.value) as f64;
+        let mut scores = ScoreBoard::<Backgammon>::new();
+        scores.insert(Some(p), cube * (2.0 * win - 1.0));
+        scores.insert(Some(!p), cube * (1.0 - 2.0 * win));
+        scores.insert(None, 0.0);
+        Some(scores)
+    }
+}
+
+// The player's pieces in their home board, None if any are outside it
+pub fn home_board(s: &Backgammon, p: bool) -> Option<Home> {
+    let mut home = [0; 6];
+    for (n, &(white, black)) in s.counts.iter().enumerate() {
+        let count = if p { white.0 } else { black.0 };
+        // The bar is index 0 and home index 25 for both players
+        let distance = match n {
+            0 => 25,
+            25 => 0,
+            n => 25 - travelled(Board(Point(n as u8)), p),
+        };
+        if count == 0 || distance == 0 {
+            continue;
+        }
+        if distance > 6 {
+            return None;
+        }
+        home[distance as usize - 1] += count;
+    }
+    Some(home)
+}
+
+// Every position the dice can lead to, using them in order
+fn plays(home: Home, dice: &[u8]) -> Vec<Home> {
+    let mut results = vec![home];
+    for &die in dice {
+        let die = die as usize;
+        let mut next = Vec::new();
+        for r in results {
+            let highest = (0..6).rev().find(|&i| r[i] > 0);
+            for i in 0..6 {
+                // Overshooting is only allowed from the highest point
+                if r[i] == 0 || (i + 1 < die && highest != Some(i)) {
+                    continue;
+                }
+                let mut n = r;
+                n[i] -= 1;
+                if i + 1 > die {
+                    n[i - die] += 1;
+                }
+                next.push(n);
+            }
+            if highest.is_none() {
+                next.push(r);
+            }
+        }
+        next.sort();
+        next.dedup();
+        results = next;
+    }
+    results
+}
+
+// The 21 different rolls with their chances
+fn rolls_and_chances() -> Vec<((u8, u8), f64)> {
+    let mut v = Vec::with_capacity(21);
+    for x in 1..7 {
+        for y in 1..(x + 1) {
+            v.push(((x, y), if x == y { 1.0 / 36.0 } else { 2.0 / 36.0 }))
+        }
+    }
+    v
+}
+
+// Every position a roll can lead to, playing the dice in either order
+pub fn roll_plays(home: Home, (x, y): (u8, u8)) -> Vec<Home> {
+    if x == y {
+        return plays(home, &[x, x, x, x]);
+    }
+    let mut results = plays(home, &[x, y]);
+    results.extend(plays(home, &[y, x]));
+    results.sort();
+    results.dedup();
+    results
+}
+
+fn pieces(home: Home) -> u8 {
+    home.iter().sum()
+}
+
+fn pip_count(home: Home) -> u32 {
+    home.iter().enumerate().map(|(i, &n)| (i as u32 + 1) * n as u32).sum()
+}
+
+// Positions in the order of their rank
+pub fn positions(checkers: u8) -> Vec<Home> {
+    fn fill(home: &mut Home, point: usize, left: u8, v: &mut Vec<Home>) {
+        if point == 6 {
+            v.push(*home);
+            return;
+        }
+        for n in 0..(left + 1) {
+            home[point] = n;
+            fill(home, point + 1, left - n, v);
+        }
+        home[point] = 0;
+    }
+    let mut v = Vec::with_capacity(positions_count(checkers));
+    fill(&mut [0; 6], 0, checkers, &mut v);
+    v
+}
+
+// Indices of the positions, those with fewer pips first
+fn by_pips(positions: &[Home]) -> Vec<usize> {
+    let mut v: Vec<_> = (0..positions.len()).collect();
+    v.sort_by_key(|&i| pip_count(positions[i]));
+    v
+}
+
+// Number of ways to place up to `checkers` pieces on `points` points
+fn combinations(checkers: u8, points: usize) -> usize {
+    // The binomial coefficient (checkers + points) choose points
+    (1..(points + 1)).fold(1, |c, k| c * (checkers as usize + k) / k)
+}
+
+fn positions_count(checkers: u8) -> usize {
+    combinations(checkers, 6)
+}
+
+// Index of the position among all positions with up to `checkers` pieces,
+// ordered by the pieces on each point in turn
+pub fn rank(home: Home, checkers: u8) -> usize {
+    let mut r = 0;
+    let mut left = checkers;
+    for (i, &n) in home.iter().enumerate() {
+        for v in 0..n {
+            r += combinations(left - v, 5 - i);
+        }
+        left -= n;
+    }
+    r
+}
+
+fn load<P, F>(path: P, kind: u8, expected_len: F) -> io::Result<(u8, Vec<u16>)>
+where
+    P: AsRef<Path>,
+    F: Fn(u8) -> usize,
+{
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    let invalid = |e: &str| io::Error::new(io::ErrorKind::InvalidData, e);
+    if bytes.len() < 6 || &bytes[..4] != MAGIC || bytes[4] != kind {
+        Err(invalid("Not a bearoff database of the right kind"))?;
+    }
+    let checkers = bytes[5];
+    if checkers > 15 || (bytes.len() - 6) != 2 * expected_len(checkers) {
+        Err(invalid("Bearoff database has the wrong size"))?;
+    }
+    let values = bytes[6..]
+        .chunks(2)
+        .map(|b| b[0] as u16 | (b[1] as u16) << 8)
+        .collect();
+    Ok((checkers, values))
+}
+
+// A small header followed by the values as little endian 16 bit integers
+fn save<P: AsRef<Path>>(path: P, kind: u8, checkers: u8, values: &[u16]) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(6 + 2 * values.len());
+    bytes.extend(MAGIC);
+    bytes.push(kind);
+    bytes.push(checkers);
+    for &v in values {
+        bytes.push(v as u8);
+        bytes.push((v >> 8) as u8);
+    }
+    File::create(path)?.write_all(&bytes)
+}
@@ -0,0 +1,101 @@
+// Where the dice come from when a game is played out
+use arena::Chance;
+use game::Notation;
+use play::{Choice, Controller, Kind};
+use super::{Backgammon, Move, Roll};
+use super::notation::parse_roll;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+pub trait Dice {
+    // Highest die first
+    fn roll(&mut self) -> io::Result<Roll>;
+}
+
+pub struct RandomDice(XorShiftRng);
+
+impl RandomDice {
+    // The same seed gives the same rolls
+    pub fn new(seed: u32) -> Self {
+        RandomDice(XorShiftRng::from_seed([seed, 0x2545_f491, 0x9e37_79b9, 0x7f4a_7c15]))
+    }
+}
+
+impl Dice for RandomDice {
+    fn roll(&mut self) -> io::Result<Roll> {
+        let (x, y) = (self.0.gen_range(1, 7), self.0.gen_range(1, 7));
+        Ok((x.max(y), x.min(y)))
+    }
+}
+
+// Rolls decided in advance, an error once they run out
+#[derive(PartialEq, Clone, Debug)]
+pub struct ReplayDice {
+    rolls: Vec<Roll>,
+    next: usize,
+}
+
+impl ReplayDice {
+    pub fn new(rolls: Vec<Roll>) -> Self {
+        ReplayDice {
+            rolls: rolls,
+            next: 0,
+        }
+    }
+
+    // Rolls such as "65" or "6-5" separated by white space,
+    // lines starting with '#' are comments
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let rolls = s.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.starts_with('#'))
+            .flat_map(|l| l.split_whitespace())
+            .map(parse_roll)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(rolls))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+        Self::parse(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Dice for ReplayDice {
+    fn roll(&mut self) -> io::Result<Roll> {
+        let roll = self.rolls.get(self.next).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "No more rolls to replay")
+        })?;
+        self.next += 1;
+        Ok(roll)
+    }
+}
+
+// Rolls for the arena, drawn from the arena's own generator
+pub struct FairDice;
+
+impl Chance<Backgammon> for FairDice {
+    fn chance_move(&self, _: &Backgammon, rng: &mut XorShiftRng) -> Move {
+        let (x, y) = (rng.gen_range(1, 7), rng.gen_range(1, 7));
+        Move::Roll((x.max(y), x.min(y)))
+    }
+}
+
+// Dice taking the seat of the player rolling them in the play loop
+pub struct Roller(pub Box<Dice>);
+
+impl Controller<Backgammon> for Roller {
+    fn kind(&self) -> Kind {
+        Kind::Chance
+    }
+
+    fn choose(&mut self, s: &Backgammon) -> io::Result<Choice<Move>> {
+        let m = Move::Roll(self.0.roll()?);
+        println!("The dice show {}", s.format_move(&m));
+        Ok(Choice::Move(m))
+    }
+}
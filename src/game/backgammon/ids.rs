@@ -0,0 +1,453 @@
+// Position and match identifiers used by GNU Backgammon and eXtreme Gammon.
+// GNU Backgammon numbers its players 0 and 1, here white is player 0 and black player 1.
+// eXtreme Gammon calls the bottom player X, which is black as its points are numbered the same way,
+// and the top player O, which is white
+use game::GameState;
+use super::{Backgammon, Cube, Phase, StackHeight, Point};
+use super::Location::{self, Bar, Board, Home};
+use super::matchplay::{BackgammonMatch, Crawford};
+
+const BASE64: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Match details not part of a single game
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+struct MatchInfo {
+    // 0 for money games
+    length: u32,
+    // Points of (white, black)
+    score: (u32, u32),
+    crawford: bool,
+}
+
+const MONEY: MatchInfo = MatchInfo {
+    length: 0,
+    score: (0, 0),
+    crawford: false,
+};
+
+impl Backgammon {
+    // GNU Backgammon Position ID, the layout of the pieces seen by the player on roll
+    pub fn position_id(&self) -> String {
+        let mut bits = Vec::with_capacity(80);
+        for &p in &[!self.player, self.player] {
+            for l in own_locations(p) {
+                for _ in 0..self.pieces(l, p) {
+                    bits.push(true);
+                }
+                bits.push(false);
+            }
+        }
+        bits.resize(80, false);
+        encode_base64(&bytes_from_bits(&bits))
+    }
+
+    // Pieces not on the board or bar are taken to be home
+    pub fn from_position_id(id: &str, player: bool) -> Result<Self, String> {
+        let bytes = decode_base64(id)?;
+        if bytes.len() != 10 {
+            Err(format!("A position ID has 14 characters, not {}", id.len()))?;
+        }
+        let mut s = Backgammon::new();
+        for count in &mut s.counts {
+            *count = (StackHeight(0), StackHeight(0));
+        }
+        s.player = player;
+        let mut bits = bits_from_bytes(&bytes).into_iter();
+        for &p in &[!player, player] {
+            let mut total = 0;
+            for l in own_locations(p) {
+                let mut n = 0;
+                while bits.next().ok_or("The position ID has too many pieces")? {
+                    n += 1;
+                }
+                s.set_pieces(l, p, n);
+                total += n;
+            }
+            if total > 15 {
+                Err("A player can't have more than 15 pieces")?;
+            }
+            s.set_pieces(Home, p, 15 - total);
+        }
+        Ok(s)
+    }
+
+    // GNU Backgammon Match ID for a money game
+    pub fn match_id(&self) -> String {
+        match_id(self, MONEY)
+    }
+
+    // Position ID and Match ID separated by a colon
+    pub fn gnubg_id(&self) -> String {
+        format!("{}:{}", self.position_id(), self.match_id())
+    }
+
+    pub fn from_gnubg_id(id: &str) -> Result<Self, String> {
+        from_gnubg_id(id).map(|(s, _)| s)
+    }
+
+    // eXtreme Gammon ID for a money game, finished games can't be represented
+    pub fn xgid(&self) -> String {
+        xgid(self, MONEY)
+    }
+
+    pub fn from_xgid(id: &str) -> Result<Self, String> {
+        from_xgid(id).map(|(s, _)| s)
+    }
+}
+
+impl BackgammonMatch {
+    pub fn gnubg_id(&self) -> String {
+        format!("{}:{}", self.game.position_id(), match_id(&self.game, self.info()))
+    }
+
+    pub fn from_gnubg_id(id: &str) -> Result<Self, String> {
+        from_gnubg_id(id).and_then(|(s, info)| Self::with_info(s, info))
+    }
+
+    pub fn xgid(&self) -> String {
+        xgid(&self.game, self.info())
+    }
+
+    pub fn from_xgid(id: &str) -> Result<Self, String> {
+        from_xgid(id).and_then(|(s, info)| Self::with_info(s, info))
+    }
+
+    fn info(&self) -> MatchInfo {
+        MatchInfo {
+            length: self.length,
+            score: self.score,
+            crawford: self.crawford == Crawford::Game,
+        }
+    }
+
+    fn with_info(game: Backgammon, info: MatchInfo) -> Result<Self, String> {
+        if info.length == 0 {
+            Err("Not a match")?;
+        }
+        let mut m = BackgammonMatch::with_length(info.length);
+        let one_away = info.score.0 + 1 == info.length || info.score.1 + 1 == info.length;
+        m.crawford = if info.crawford {
+            Crawford::Game
+        } else if one_away {
+            Crawford::After
+        } else {
+            Crawford::Before
+        };
+        m.score = info.score;
+        m.game = game;
+        Ok(m)
+    }
+}
+
+fn match_id(s: &Backgammon, info: MatchInfo) -> String {
+    let mut bits = Vec::with_capacity(72);
+    let cube = s.cube.unwrap_or(Cube {
+        value: 1,
+        owner: None,
+    });
+    push_number(&mut bits, log2(cube.value), 4);
+    push_number(&mut bits, cube.owner.map_or(3, gnubg_player), 2);
+    push_number(&mut bits, gnubg_player(s.player), 1);
+    push_number(&mut bits, info.crawford as u32, 1);
+    let state = match s.phase {
+        Phase::Dropped => 4,
+        _ if s.finished() => 2,
+        _ => 1,
+    };
+    push_number(&mut bits, state, 3);
+    let turn = s.current_player().unwrap_or(s.player);
+    push_number(&mut bits, gnubg_player(turn), 1);
+    push_number(&mut bits, (s.phase == Phase::Take) as u32, 1);
+    // Resignations aren't supported
+    push_number(&mut bits, 0, 2);
+    let dice = if s.phase == Phase::Play { s.dice } else { (0, 0) };
+    push_number(&mut bits, dice.0 as u32, 3);
+    push_number(&mut bits, dice.1 as u32, 3);
+    push_number(&mut bits, info.length, 15);
+    push_number(&mut bits, info.score.0, 15);
+    push_number(&mut bits, info.score.1, 15);
+    // GNU Backgammon pads the rest with zeros, here it records what its IDs leave out,
+    // that the game has no cube or that the player on roll has chosen not to double.
+    // IDs from GNU Backgammon read as games with a cube, waiting for the player to decide
+    push_number(&mut bits, s.cube.is_none() as u32, 1);
+    push_number(&mut bits, (s.phase == Phase::Roll && s.may_double()) as u32, 1);
+    bits.resize(72, false);
+    encode_base64(&bytes_from_bits(&bits))
+}
+
+fn from_gnubg_id(id: &str) -> Result<(Backgammon, MatchInfo), String> {
+    let mut parts = id.trim().splitn(2, ':');
+    let position = parts.next().ok_or("Missing position ID")?;
+    let bytes = decode_base64(parts.next().ok_or("Missing match ID")?)?;
+    if bytes.len() != 9 {
+        Err("A match ID has 12 characters")?;
+    }
+    let bits = bits_from_bytes(&bytes);
+    let mut read = {
+        let mut i = 0;
+        move |width: usize| {
+            let n = number(&bits[i..(i + width)]);
+            i += width;
+            n
+        }
+    };
+    let cube_value = 1 << read(4);
+    let cube_owner = match read(2) {
+        3 => None,
+        p => Some(our_player(p)),
+    };
+    let player = our_player(read(1));
+    let crawford = read(1) == 1;
+    let state = read(3);
+    let _turn = read(1);
+    let doubled = read(1) == 1;
+    let _resignation = read(2);
+    let dice = (read(3) as u8, read(3) as u8);
+    let info = MatchInfo {
+        length: read(15),
+        score: (read(15), read(15)),
+        crawford: crawford,
+    };
+    let cubeless = read(1) == 1;
+    let no_double = read(1) == 1;
+    let mut s = Backgammon::from_position_id(position, player)?;
+    s.cube = if crawford || cubeless {
+        None
+    } else {
+        Some(Cube {
+            value: cube_value,
+            owner: cube_owner,
+        })
+    };
+    s.dice = dice;
+    s.phase = if state == 4 {
+        Phase::Dropped
+    } else if doubled {
+        Phase::Take
+    } else if dice != (0, 0) {
+        Phase::Play
+    } else if s.may_double() && !no_double {
+        Phase::Cube
+    } else {
+        Phase::Roll
+    };
+    Ok((s, info))
+}
+
+// Players who have chosen not to double are written as still deciding, as XGIDs can't tell
+fn xgid(s: &Backgammon, info: MatchInfo) -> String {
+    let mut position = String::with_capacity(26);
+    position.push(xg_pieces(s.pieces(Bar, true), true));
+    for n in 1..25 {
+        let l = Board(Point(n));
+        position.push(match (s.pieces(l, false), s.pieces(l, true)) {
+            (0, white) => xg_pieces(white, true),
+            (black, _) => xg_pieces(black, false),
+        });
+    }
+    position.push(xg_pieces(s.pieces(Bar, false), false));
+    let (cube, cube_position, max_cube) = match s.cube {
+        None => (1, 0, 0),
+        Some(c) => (c.value, c.owner.map_or(0, xg_player), 10),
+    };
+    let dice = match s.phase {
+        Phase::Take => "D".to_string(),
+        Phase::Play => format!("{}{}", s.dice.0, s.dice.1),
+        _ => "00".to_string(),
+    };
+    format!(
+        "XGID={}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+        position,
+        log2(cube),
+        cube_position,
+        xg_player(s.player),
+        dice,
+        info.score.1,
+        info.score.0,
+        info.crawford as u8,
+        info.length,
+        max_cube
+    )
+}
+
+fn from_xgid(id: &str) -> Result<(Backgammon, MatchInfo), String> {
+    let id = id.trim();
+    let id = if id.starts_with("XGID=") { &id[5..] } else { id };
+    let fields: Vec<_> = id.split(':').collect();
+    if fields.len() != 10 {
+        Err(format!("An XGID has 10 fields, not {}", fields.len()))?;
+    }
+    let number = |i: usize| fields[i].parse::<i32>().map_err(|e| format!("{}: {}", fields[i], e));
+    let mut s = Backgammon::new();
+    for count in &mut s.counts {
+        *count = (StackHeight(0), StackHeight(0));
+    }
+    let position: Vec<_> = fields[0].chars().collect();
+    if position.len() != 26 {
+        Err("An XGID position has 26 characters")?;
+    }
+    for (i, &c) in position.iter().enumerate() {
+        let (n, white) = match c {
+            '-' => continue,
+            c if c >= 'A' && c <= 'P' => (c as u8 - b'A' + 1, false),
+            c if c >= 'a' && c <= 'p' => (c as u8 - b'a' + 1, true),
+            _ => Err(format!("Unexpected {} in XGID position", c))?,
+        };
+        let l = match i {
+            0 | 25 => Bar,
+            n => Board(Point(n as u8)),
+        };
+        if l == Bar && white != (i == 0) {
+            Err("Pieces on a bar belong to the wrong player")?;
+        }
+        s.set_pieces(l, white, n);
+    }
+    for &p in &[false, true] {
+        let total: u8 = own_locations(p).into_iter().map(|l| s.pieces(l, p)).sum();
+        if total > 15 {
+            Err("A player can't have more than 15 pieces")?;
+        }
+        s.set_pieces(Home, p, 15 - total);
+    }
+    let cube = 1 << number(1)?;
+    let owner = match number(2)? {
+        0 => None,
+        p => Some(p < 0),
+    };
+    s.player = number(3)? < 0;
+    let info = MatchInfo {
+        length: number(8)? as u32,
+        score: (number(6)? as u32, number(5)? as u32),
+        crawford: number(8)? > 0 && number(7)? == 1,
+    };
+    s.cube = if number(9)? == 0 || info.crawford {
+        None
+    } else {
+        Some(Cube {
+            value: cube,
+            owner: owner,
+        })
+    };
+    s.phase = match fields[4] {
+        "D" => Phase::Take,
+        "00" => if s.may_double() {
+            Phase::Cube
+        } else {
+            Phase::Roll
+        },
+        dice => {
+            let dice: Vec<_> = dice.chars()
+                .filter_map(|c| c.to_digit(10))
+                .filter(|&n| n >= 1 && n <= 6)
+                .map(|n| n as u8)
+                .collect();
+            if dice.len() != 2 || fields[4].len() != 2 {
+                Err(format!("Unexpected dice {} in XGID", fields[4]))?;
+            }
+            s.dice = (dice[0].max(dice[1]), dice[0].min(dice[1]));
+            Phase::Play
+        }
+    };
+    Ok((s, info))
+}
+
+// Points from the player's own 1 point to their 24 point, then the bar
+fn own_locations(p: bool) -> Vec<Location> {
+    let mut v: Vec<_> = (1..25)
+        .map(|n| Board(Point(if p { 25 - n } else { n })))
+        .collect();
+    v.push(Bar);
+    v
+}
+
+fn gnubg_player(p: bool) -> u32 {
+    if p { 0 } else { 1 }
+}
+
+fn our_player(n: u32) -> bool {
+    n == 0
+}
+
+fn xg_player(p: bool) -> i32 {
+    if p { -1 } else { 1 }
+}
+
+fn xg_pieces(n: u8, white: bool) -> char {
+    match n {
+        0 => '-',
+        n => ((if white { b'a' } else { b'A' }) + n - 1) as char,
+    }
+}
+
+fn log2(n: u32) -> u32 {
+    31 - n.leading_zeros()
+}
+
+// Least significant bit first, the order GNU Backgammon uses
+fn push_number(bits: &mut Vec<bool>, n: u32, width: usize) {
+    for i in 0..width {
+        bits.push(n >> i & 1 == 1);
+    }
+}
+
+fn number(bits: &[bool]) -> u32 {
+    bits.iter()
+        .enumerate()
+        .map(|(i, &b)| (b as u32) << i)
+        .sum()
+}
+
+fn bytes_from_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8).map(|byte| number(byte) as u8).collect()
+}
+
+fn bits_from_bytes(bytes: &[u8]) -> Vec<bool> {
+    let mut bits = Vec::with_capacity(bytes.len() * 8);
+    for &byte in bytes {
+        push_number(&mut bits, byte as u32, 8);
+    }
+    bits
+}
+
+// Standard base64 without padding
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| (b as u32) << (16 - 8 * i))
+            .sum::<u32>();
+        for i in 0..(chunk.len() + 1) {
+            s.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    s
+}
+
+fn decode_base64(s: &str) -> Result<Vec<u8>, String> {
+    let digits = s.trim_end_matches('=')
+        .bytes()
+        .map(|c| {
+            BASE64
+                .iter()
+                .position(|&d| d == c)
+                .ok_or(format!("{} is not base64", c as char))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut bytes = Vec::new();
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            Err("Truncated base64")?;
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| (d as u32) << (18 - 6 * i))
+            .sum::<u32>();
+        for i in 0..(chunk.len() - 1) {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,166 @@
+use game::{GameState, Score};
+use super::{Backgammon, Move, Player};
+use super::met::MatchEquityTable;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Crawford {
+    Before,
+    // The game right after a player gets within one point of winning is played without a cube
+    Game,
+    After,
+}
+
+// A match to `length` points, played one game at a time.
+// Its scores are chances of winning the match rather than points,
+// so search optimizes for winning the match
+#[derive(Clone, Debug)]
+pub struct BackgammonMatch {
+    pub game: Backgammon,
+    pub length: u32,
+    // Points won so far by (white, black)
+    pub score: (u32, u32),
+    pub crawford: Crawford,
+    met: Arc<MatchEquityTable>,
+}
+
+// The table is fixed for the whole match, so it doesn't distinguish states
+impl PartialEq for BackgammonMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.game == other.game && self.length == other.length && self.score == other.score &&
+            self.crawford == other.crawford
+    }
+}
+
+impl Eq for BackgammonMatch {}
+
+impl Hash for BackgammonMatch {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.game.hash(state);
+        self.length.hash(state);
+        self.score.hash(state);
+        self.crawford.hash(state);
+    }
+}
+
+impl GameState for BackgammonMatch {
+    type Move = Move;
+    type Player = Player;
+
+    fn new() -> Self {
+        Self::with_length(7)
+    }
+
+    fn apply(&mut self, m: Self::Move) {
+        self.game.apply(m)
+    }
+
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        self.game.legal_moves()
+    }
+
+    fn players() -> Vec<Self::Player> {
+        Backgammon::players()
+    }
+
+    fn current_player(&self) -> Self::Player {
+        self.game.current_player()
+    }
+
+    fn scores(&self) -> Option<HashMap<Self::Player, Score>> {
+        self.game.scores().map(|_| {
+            let (score, crawford) = self.score_after_game();
+            let white = self.met.win_chance(
+                self.length.saturating_sub(score.0),
+                self.length.saturating_sub(score.1),
+                crawford == Crawford::After,
+            );
+            let mut m = HashMap::new();
+            m.insert(Some(true), white);
+            m.insert(Some(false), 1.0 - white);
+            m.insert(None, 0.0);
+            m
+        })
+    }
+
+    fn finished(&self) -> bool {
+        self.game.finished()
+    }
+}
+
+impl BackgammonMatch {
+    pub fn with_length(length: u32) -> Self {
+        Self::with_table(length, Arc::new(MatchEquityTable::generated(length)))
+    }
+
+    pub fn with_table(length: u32, met: Arc<MatchEquityTable>) -> Self {
+        BackgammonMatch {
+            game: Self::game_for(Crawford::Before),
+            length: length,
+            score: (0, 0),
+            crawford: Crawford::Before,
+            met: met,
+        }
+    }
+
+    // The match as it continues after the current game,
+    // None if the game isn't over or the match has been won
+    pub fn next_game(&self) -> Option<Self> {
+        if !self.game.finished() || self.winner().is_some() {
+            return None;
+        }
+        let (score, crawford) = self.score_after_game();
+        let mut next = self.clone();
+        next.score = score;
+        next.crawford = crawford;
+        next.game = Self::game_for(crawford);
+        if next.winner().is_some() {
+            None
+        } else {
+            Some(next)
+        }
+    }
+
+    // The player who has won the match, if any
+    pub fn winner(&self) -> Option<bool> {
+        let (score, _) = self.score_after_game();
+        if score.0 >= self.length {
+            Some(true)
+        } else if score.1 >= self.length {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn game_for(crawford: Crawford) -> Backgammon {
+        if crawford == Crawford::Game {
+            Backgammon::new()
+        } else {
+            Backgammon::with_cube()
+        }
+    }
+
+    // Match score and Crawford stage once the current game has been scored
+    fn score_after_game(&self) -> ((u32, u32), Crawford) {
+        let mut score = self.score;
+        let mut crawford = self.crawford;
+        if let Some(scores) = self.game.scores() {
+            let white = scores[&Some(true)];
+            if white > 0.0 {
+                score.0 += white as u32;
+            } else {
+                score.1 += -white as u32;
+            }
+            let one_away = |points: u32| points + 1 == self.length;
+            crawford = match crawford {
+                Crawford::Before if one_away(score.0) || one_away(score.1) => Crawford::Game,
+                Crawford::Game => Crawford::After,
+                c => c,
+            };
+        }
+        (score, crawford)
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+// Chances of winning a single, gammon and backgammon for one side in one game.
+// Roughly the rates seen in money play, doubled they sum to 1
+const OUTCOMES: [(u32, f64); 3] = [(1, 0.36), (2, 0.13), (3, 0.01)];
+
+// Chance of winning the match depending on how many points each player still needs
+#[derive(PartialEq, Clone, Debug)]
+pub struct MatchEquityTable {
+    // pre[i][j] is for a player needing i + 1 points against one needing j + 1,
+    // any player needing 1 point is playing the Crawford game
+    pre: Vec<Vec<f64>>,
+    // post[j] is for a player needing j + 1 points after the Crawford game
+    // against one needing 1 point
+    post: Vec<f64>,
+}
+
+impl MatchEquityTable {
+    // Table for matches up to `size` points,
+    // modelling every game as played without a cube
+    // except after the Crawford game, where the trailer doubles straight away.
+    // DISCUSS bundle a published table instead?
+    pub fn generated(size: u32) -> Self {
+        let size = size as usize;
+        let mut post = vec![0.0; size];
+        for j in 0..size {
+            post[j] = if j == 0 {
+                0.5
+            } else {
+                OUTCOMES
+                    .iter()
+                    .map(|&(points, p)| {
+                        let left = j as i64 + 1 - 2 * points as i64;
+                        p * if left <= 0 { 1.0 } else { post[left as usize - 1] }
+                    })
+                    .sum()
+            };
+        }
+        let mut pre = vec![vec![0.0; size]; size];
+        // Filling by increasing total guarantees both smaller scores are known
+        for total in 0..(2 * size) {
+            for i in 0..size {
+                if total < i || total - i >= size {
+                    continue;
+                }
+                let j = total - i;
+                pre[i][j] = if i == 0 && j == 0 {
+                    0.5
+                } else if i == 0 {
+                    // The Crawford game: the trailer reaches post-Crawford play by winning
+                    0.5 +
+                        OUTCOMES
+                            .iter()
+                            .map(|&(points, p)| {
+                                let left = j as i64 + 1 - points as i64;
+                                p * if left <= 0 { 0.0 } else { 1.0 - post[left as usize - 1] }
+                            })
+                            .sum::<f64>()
+                } else if j == 0 {
+                    1.0 - pre[0][i]
+                } else {
+                    OUTCOMES
+                        .iter()
+                        .map(|&(points, p)| {
+                            let (i_left, j_left) = (i as i64 + 1 - points as i64, j as i64 + 1 - points as i64);
+                            let win = if i_left <= 0 { 1.0 } else { pre[i_left as usize - 1][j] };
+                            let loss = if j_left <= 0 { 0.0 } else { pre[i][j_left as usize - 1] };
+                            p * (win + loss)
+                        })
+                        .sum()
+                };
+            }
+        }
+        MatchEquityTable {
+            pre: pre,
+            post: post,
+        }
+    }
+
+    // Largest number of points the table covers
+    pub fn size(&self) -> u32 {
+        self.post.len() as u32
+    }
+
+    // Chance of winning the match for a player needing `away` points
+    // against one needing `opponent_away` points.
+    // Scores beyond the size of the table are treated as being at its edge
+    pub fn win_chance(&self, away: u32, opponent_away: u32, post_crawford: bool) -> f64 {
+        let size = self.size();
+        match (away.min(size), opponent_away.min(size)) {
+            (0, _) => 1.0,
+            (_, 0) => 0.0,
+            (1, 1) => 0.5,
+            (1, o) if post_crawford => 1.0 - self.post[o as usize - 1],
+            (a, 1) if post_crawford => self.post[a as usize - 1],
+            (a, o) => self.pre[a as usize - 1][o as usize - 1],
+        }
+    }
+
+    // Plain text format: one row of the pre-Crawford table per line,
+    // then a line saying "post-crawford" followed by the post-Crawford row.
+    // Lines starting with '#' are comments
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut pre = Vec::new();
+        let mut post = None;
+        for line in s.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "post-crawford" {
+                post = Some(Vec::new());
+                continue;
+            }
+            let row = line.split_whitespace()
+                .map(|x| x.parse::<f64>().map_err(|e| format!("{}: {}", x, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+            match post {
+                None => pre.push(row),
+                Some(ref mut post) => {
+                    if !post.is_empty() {
+                        Err("Only one post-Crawford row is allowed")?;
+                    }
+                    post.extend(row)
+                }
+            }
+        }
+        let post = post.ok_or("Missing post-Crawford row")?;
+        if pre.is_empty() || post.len() != pre.len() || pre.iter().any(|row| row.len() != pre.len()) {
+            Err("The pre-Crawford table must be square and as wide as the post-Crawford row")?;
+        }
+        Ok(MatchEquityTable {
+            pre: pre,
+            post: post,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+        Self::parse(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut f = File::create(path)?;
+        writeln!(f, "# Chance of winning for a player needing row + 1 points against column + 1")?;
+        for row in &self.pre {
+            writeln!(f, "{}", format_row(row))?;
+        }
+        writeln!(f, "post-crawford")?;
+        writeln!(f, "{}", format_row(&self.post))
+    }
+}
+
+fn format_row(row: &[f64]) -> String {
+    row.iter()
+        .map(|x| format!("{:.4}", x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
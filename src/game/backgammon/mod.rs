@@ -1,3 +1,16 @@
+pub mod met;
+pub mod matchplay;
+pub mod ids;
+pub mod records;
+pub mod notation;
+pub mod analysis;
+pub mod bearoff;
+pub mod network;
+pub mod rollout;
+pub mod review;
+pub mod dice;
+pub mod render;
+
 use game::{GameState, Score};
 use std::collections::HashMap;
 use std::fmt;
@@ -37,7 +50,7 @@ impl fmt::Display for Location {
     }
 }
 
-// This needs type ascription, is there a better way to index a `Vec` by location?
+// Indexes `counts` with `usize::from(l)`, see also `pieces`.
 // Ideally, this would depend on the player, making locations relative
 impl From<Location> for usize {
     fn from(x: Location) -> usize {
@@ -54,21 +67,58 @@ impl From<Location> for usize {
 // but home and bar are exceptions to that
 type Count = (StackHeight, StackHeight);
 
+// No doubles are offered once the cube shows this value
+pub const MAX_CUBE: u32 = 64;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct Cube {
+    pub value: u32,
+    // None while the cube is in the middle
+    pub owner: Option<bool>,
+}
+
+// What the game is waiting for
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+enum Phase {
+    // The player on roll may double before rolling
+    Cube,
+    Roll,
+    Play,
+    // The opponent of the player on roll has been offered a double
+    Take,
+    Dropped,
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct Backgammon {
     pub player: bool,
-    roll_turn: bool,
+    phase: Phase,
     pub counts: Vec<Count>,
     pub dice: Roll,
+    // None when playing without a doubling cube
+    pub cube: Option<Cube>,
 }
 
 // Location, amount to move by
 pub type SingleMove = (Location, u8);
 
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum CubeAction {
+    NoDouble,
+    Double,
+    Take,
+    Drop,
+}
+
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Debug)]
+pub enum Move {
+    Roll(Roll),
+    Play(Vec<SingleMove>),
+    Cube(CubeAction),
+}
+
 impl GameState for Backgammon {
-    // Result is used to make a sum type
-    // deliberately "privileging" the player turns over the dice turns
-    type Move = Result<Vec<SingleMove>, Roll>;
+    type Move = Move;
     type Player = Player;
 
     fn new() -> Self {
@@ -93,22 +143,26 @@ impl GameState for Backgammon {
         }
         Backgammon {
             player: true,
-            roll_turn: true,
+            phase: Phase::Roll,
             counts: v,
             dice: (0, 0),
+            cube: None,
         }
     }
 
     fn apply(&mut self, m: Self::Move) {
         match m {
-            Ok(v) => {
-                // Assumes causally consistent ordering
+            Move::Play(mut v) => {
+                // Canonical moves are sorted by location rather than causally,
+                // so apply them in the order the player travels
                 // otherwise points may underflow
                 // (if an empty point is moved from, then moved to)
+                let player = self.player;
+                v.sort_by_key(|&(l, _)| travelled(l, player));
                 for (l, n) in v {
                     // Non-lexical lifetimes would be nice here
                     {
-                        let from = &mut self.counts[l.into(): usize];
+                        let from = &mut self.counts[usize::from(l)];
                         if self.player {
                             (from.0).0 -= 1
                         } else {
@@ -116,9 +170,9 @@ impl GameState for Backgammon {
                         };
                     }
                     let to_index = if self.player {
-                        (if l == Bar { 0 } else { l.into(): usize }) as i8 + n as i8
+                        (if l == Bar { 0 } else { usize::from(l) }) as i8 + n as i8
                     } else {
-                        (if l == Bar { 25 } else { l.into(): usize }) as i8 - n as i8
+                        (if l == Bar { 25 } else { usize::from(l) }) as i8 - n as i8
                     };
                     let home_p = if self.player {
                         to_index > 24
@@ -138,7 +192,7 @@ impl GameState for Backgammon {
                     };
                     // Another non-lexical lifetime
                     {
-                        let mut to = &mut self.counts[to_pos.into(): usize];
+                        let mut to = &mut self.counts[usize::from(to_pos)];
                         if self.player {
                             (to.0).0 += 1
                         } else {
@@ -153,7 +207,7 @@ impl GameState for Backgammon {
                         }
                     }
                     if enemy_hit {
-                        let mut bar = &mut self.counts[Bar.into(): usize];
+                        let mut bar = &mut self.counts[usize::from(Bar)];
                         if self.player {
                             (bar.1).0 += 1;
                         } else {
@@ -161,13 +215,30 @@ impl GameState for Backgammon {
                         };
                     }
                 }
-                self.roll_turn = true;
                 self.player = !self.player;
+                // Stale dice would tell otherwise equal states apart
+                self.dice = (0, 0);
+                self.phase = if self.may_double() {
+                    Phase::Cube
+                } else {
+                    Phase::Roll
+                };
             }
-            Err(pair) => {
-                self.roll_turn = false;
+            Move::Roll(pair) => {
+                self.phase = Phase::Play;
                 self.dice = pair;
             }
+            Move::Cube(CubeAction::NoDouble) => self.phase = Phase::Roll,
+            Move::Cube(CubeAction::Double) => self.phase = Phase::Take,
+            Move::Cube(CubeAction::Take) => {
+                let taker = !self.player;
+                self.cube.as_mut().map(|c| {
+                    c.value *= 2;
+                    c.owner = Some(taker);
+                });
+                self.phase = Phase::Roll;
+            }
+            Move::Cube(CubeAction::Drop) => self.phase = Phase::Dropped,
         }
     }
 
@@ -176,24 +247,30 @@ impl GameState for Backgammon {
     }
 
     fn current_player(&self) -> Self::Player {
-        if self.roll_turn {
-            None
-        } else {
-            Some(self.player)
+        match self.phase {
+            Phase::Roll => None,
+            Phase::Take => Some(!self.player),
+            _ => Some(self.player),
         }
     }
 
     fn legal_moves(&self) -> Vec<Self::Move> {
         let mut v = Vec::new();
-        if self.roll_turn {
+        if self.phase == Phase::Roll {
             // Canonical representation of dice rolls:
             // highest die first
             for x in 1..7 {
                 for y in 1..(x + 1) {
-                    v.push(Err((x, y)))
+                    v.push(Move::Roll((x, y)))
                 }
             }
-        } else {
+        } else if self.phase == Phase::Cube {
+            v.push(Move::Cube(CubeAction::NoDouble));
+            v.push(Move::Cube(CubeAction::Double));
+        } else if self.phase == Phase::Take {
+            v.push(Move::Cube(CubeAction::Take));
+            v.push(Move::Cube(CubeAction::Drop));
+        } else if self.phase == Phase::Play {
             let (x, y) = self.dice;
             let mut mss: Vec<Vec<_>> = Vec::new();
             mss.push(Vec::new());
@@ -203,20 +280,18 @@ impl GameState for Backgammon {
                 mss.extend(self.legal_sequences(&[x, y]).into_iter());
                 mss.extend(self.legal_sequences(&[y, x]).into_iter());
             }
-            let max_moves = mss.iter()
-                .map(|ms| ms.iter().map(|&(_, n)| n).sum::<u8>())
-                .max()
-                .unwrap_or(0);
-            mss.retain(|ms| max_moves == ms.iter().map(|&(_, n)| n).sum::<u8>());
+            // As many dice as possible must be used,
+            // and if only one of two dice can be used it must be the higher one
+            let usage = |ms: &Vec<SingleMove>| (ms.len(), ms.iter().map(|&(_, n)| n).max());
+            let max_usage = mss.iter().map(&usage).max().unwrap_or((0, None));
+            mss.retain(|ms| usage(ms) == max_usage);
             for mut ms in mss {
                 // Canonical representation of moves
                 // Lowest position (counting as white) first
-                // NOTE this breaks the causual consistency assumption of `apply`
-                // however, underflow is rectified later,
-                // so thanks to release mode having defined semantics for underflow
-                // applying an entire move sequence preserves consistency
+                // NOTE this breaks the causal ordering of the moves,
+                // which `apply` restores
                 ms.sort();
-                v.push(Ok(ms))
+                v.push(Move::Play(ms))
             }
             v.sort();
             v.dedup();
@@ -227,16 +302,23 @@ impl GameState for Backgammon {
     fn scores(&self) -> Option<HashMap<Self::Player, Score>> {
         if self.finished() {
             let mut m = HashMap::new();
-            // The person who just finished their turn is the one who has won
-            let p = !self.player;
-            let multiplier = match (self.all_homeboard(!p), self.any_loc(Home, !p)) {
-                // Backgammon
-                (false, false) => 3.0,
-                // Gammon
-                (true, false) => 2.0,
-                // DISCUSS panic if inconsistent result?
-                // this can only be (true, true)
-                _ => 1.0,
+            let cube = self.cube.map(|c| c.value).unwrap_or(1) as Score;
+            let (p, multiplier) = if self.phase == Phase::Dropped {
+                // The doubler wins what the cube was worth before the double
+                (self.player, cube)
+            } else {
+                // The person who just finished their turn is the one who has won
+                let p = !self.player;
+                let multiplier = match (self.all_homeboard(!p), self.any_loc(Home, !p)) {
+                    // Backgammon
+                    (false, false) => 3.0,
+                    // Gammon
+                    (true, false) => 2.0,
+                    // DISCUSS panic if inconsistent result?
+                    // this can only be (true, true)
+                    _ => 1.0,
+                };
+                (p, multiplier * cube)
             };
             m.insert(Some(p), multiplier);
             m.insert(Some(!p), -multiplier);
@@ -249,11 +331,59 @@ impl GameState for Backgammon {
     }
 
     fn finished(&self) -> bool {
-        self.all_loc(Home, false) || self.all_loc(Home, true)
+        self.phase == Phase::Dropped || self.all_loc(Home, false) || self.all_loc(Home, true)
     }
 }
 
 impl Backgammon {
+    // A money game, starting with the cube in the middle
+    pub fn with_cube() -> Self {
+        let mut s = <Self as GameState>::new();
+        s.cube = Some(Cube {
+            value: 1,
+            owner: None,
+        });
+        s
+    }
+
+    // A cubeless position with pieces at the given locations, as (location, white, black),
+    // the rest of them having been borne off
+    pub fn from_counts(counts: &[(Location, u8, u8)], player: bool) -> Self {
+        let mut s = <Self as GameState>::new();
+        s.counts = vec![(StackHeight(0), StackHeight(0)); 26];
+        let (mut white, mut black) = (15, 15);
+        for &(l, w, b) in counts {
+            let count = &mut s.counts[usize::from(l)];
+            (count.0).0 += w;
+            (count.1).0 += b;
+            white -= w;
+            black -= b;
+        }
+        s.counts[usize::from(Home)] = (StackHeight(white), StackHeight(black));
+        s.player = player;
+        s
+    }
+
+    // The player's pieces at the location
+    pub fn pieces(&self, l: Location, p: bool) -> u8 {
+        let count = self.counts[usize::from(l)];
+        if p { (count.0).0 } else { (count.1).0 }
+    }
+
+    fn set_pieces(&mut self, l: Location, p: bool, n: u8) {
+        let count = &mut self.counts[usize::from(l)];
+        if p {
+            count.0 = StackHeight(n)
+        } else {
+            count.1 = StackHeight(n)
+        }
+    }
+
+    // Whether the player on roll has access to the cube
+    pub fn may_double(&self) -> bool {
+        self.cube.map_or(false, |c| c.value < MAX_CUBE && c.owner != Some(!self.player))
+    }
+
     fn legal_sequences(&self, dice: &[u8]) -> Vec<Vec<SingleMove>> {
         // Clone slice into Vec
         let mut new_dice = Vec::new();
@@ -267,11 +397,11 @@ impl Backgammon {
                 let mut s = self.clone();
                 let m = (p, roll);
                 if self.legal_move(&m) {
-                    <Backgammon as GameState>::apply(&mut s, Ok(vec![m]));
+                    <Backgammon as GameState>::apply(&mut s, Move::Play(vec![m]));
                     s.player = !s.player;
                     // Recursion here is limited to depth 4
                     // and way simpler than doing backtracking
-                    for mut ms in self.legal_sequences(&new_dice) {
+                    for mut ms in s.legal_sequences(&new_dice) {
                         ms.push(m);
                         sequences.push(ms);
                     }
@@ -286,7 +416,7 @@ impl Backgammon {
     // but is hopefully cheaper and simpler than having a `legal_state` function
     fn legal_move(&self, m: &SingleMove) -> bool {
         let &(l, n) = m;
-        let from_count = self.counts[l.into(): usize];
+        let from_count = self.counts[usize::from(l)];
         let can_move_from = if self.player {
             from_count.0 >= StackHeight(1)
         } else {
@@ -295,16 +425,21 @@ impl Backgammon {
         let all_h_w = self.all_homeboard(true);
         let all_h_b = self.all_homeboard(false);
         let to_index = if self.player {
-            (if l == Bar { 0 } else { l.into(): usize }) as i8 + n as i8
+            (if l == Bar { 0 } else { usize::from(l) }) as i8 + n as i8
         } else {
-            (if l == Bar { 25 } else { l.into(): usize }) as i8 - n as i8
+            (if l == Bar { 25 } else { usize::from(l) }) as i8 - n as i8
         };
         let empty = (StackHeight(0), StackHeight(0));
         let enemy_count = self.counts.get(to_index as usize).unwrap_or(&empty);
+        // Bearing off with a higher die than needed
+        // is only allowed from the rearmost point
+        let exact_or_rearmost = |overshoot: bool| !overshoot || !self.any_behind(l, self.player);
         let can_move_to = if self.player {
-            (to_index > 24 && all_h_w) || (to_index <= 24 && enemy_count.1 <= StackHeight(1))
+            (to_index > 24 && all_h_w && exact_or_rearmost(to_index > 25)) ||
+                (to_index <= 24 && enemy_count.1 <= StackHeight(1))
         } else {
-            (to_index < 1 && all_h_b) || (to_index >= 1 && enemy_count.0 <= StackHeight(1))
+            (to_index < 1 && all_h_b && exact_or_rearmost(to_index < 0)) ||
+                (to_index >= 1 && enemy_count.0 <= StackHeight(1))
         };
         let bar_check = if self.any_loc(Bar, self.player) {
             l == Bar
@@ -318,21 +453,39 @@ impl Backgammon {
         homeboard(p)
             .into_iter()
             .map(|n| {
-                let counts = self.counts[n.into(): usize];
+                let counts = self.counts[usize::from(n)];
                 if p { (counts.0).0 } else { (counts.1).0 }
             })
             .sum::<u8>() == 15
     }
 
     fn any_loc(&self, l: Location, p: bool) -> bool {
-        let counts = self.counts[l.into(): usize];
+        let counts = self.counts[usize::from(l)];
         StackHeight(0) < if p { counts.0 } else { counts.1 }
     }
 
     fn all_loc(&self, l: Location, p: bool) -> bool {
-        let counts = self.counts[l.into(): usize];
+        let counts = self.counts[usize::from(l)];
         StackHeight(15) == if p { counts.0 } else { counts.1 }
     }
+
+    // Whether the player has pieces further from home than `l`
+    fn any_behind(&self, l: Location, p: bool) -> bool {
+        let mut positions = board();
+        positions.push(Bar);
+        positions
+            .into_iter()
+            .any(|l2| travelled(l2, p) < travelled(l, p) && self.any_loc(l2, p))
+    }
+}
+
+// How far along a piece at the location has come, from the player's point of view
+fn travelled(l: Location, p: bool) -> u8 {
+    match l {
+        Bar => 0,
+        Home => 25,
+        Board(Point(n)) => if p { n } else { 25 - n },
+    }
 }
 
 // DISCUSS more correct and less wasteful but less convenient to return boxed slice
@@ -0,0 +1,325 @@
+// A TD-Gammon style neural network, a single hidden layer of sigmoid units
+// trained by temporal difference learning from games against itself
+use game::{Evaluator, GameState, ScoreBoard};
+use super::{Backgammon, Move};
+use super::Location::{Bar, Home};
+use rand::Rng;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+// Four units per point and player, the bar, pieces borne off and whose turn it is
+pub const INPUTS: usize = 24 * 4 * 2 + 2 + 2 + 2;
+pub const OUTPUTS: usize = 5;
+
+// Chances of how the game ends, from white's point of view.
+// Gammons include backgammons
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Outcomes {
+    pub win: f64,
+    pub win_gammon: f64,
+    pub win_backgammon: f64,
+    pub lose_gammon: f64,
+    pub lose_backgammon: f64,
+}
+
+impl Outcomes {
+    // Expected points for white with the cube at 1
+    pub fn equity(&self) -> f64 {
+        2.0 * self.win - 1.0 + self.win_gammon - self.lose_gammon + self.win_backgammon - self.lose_backgammon
+    }
+
+    // What actually happened in a finished game
+    pub fn of_result(s: &Backgammon) -> Option<Self> {
+        s.scores().map(|scores| {
+            let cube = s.cube.map_or(1, |c| c.value) as f64;
+            let white = scores[&Some(true)] / cube;
+            let at_least = |points: f64| if white >= points { 1.0 } else { 0.0 };
+            let at_most = |points: f64| if white <= -points { 1.0 } else { 0.0 };
+            Outcomes {
+                win: at_least(1.0),
+                win_gammon: at_least(2.0),
+                win_backgammon: at_least(3.0),
+                lose_gammon: at_most(2.0),
+                lose_backgammon: at_most(3.0),
+            }
+        })
+    }
+
+    fn to_vec(&self) -> Vec<f64> {
+        vec![self.win, self.win_gammon, self.win_backgammon, self.lose_gammon, self.lose_backgammon]
+    }
+
+    fn from_slice(v: &[f64]) -> Self {
+        Outcomes {
+            win: v[0],
+            win_gammon: v[1],
+            win_backgammon: v[2],
+            lose_gammon: v[3],
+            lose_backgammon: v[4],
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Network {
+    hidden: usize,
+    // Row per hidden unit, the last column is the bias
+    hidden_weights: Vec<Vec<f64>>,
+    // Row per output, the last column is the bias
+    output_weights: Vec<Vec<f64>>,
+}
+
+// The state of the network for one input, kept around for learning
+struct Activations {
+    inputs: Vec<f64>,
+    hidden: Vec<f64>,
+    outputs: Vec<f64>,
+}
+
+// Eligibility traces for each output and weight
+struct Traces {
+    hidden: Vec<Vec<Vec<f64>>>,
+    output: Vec<Vec<f64>>,
+}
+
+impl Traces {
+    fn new(hidden: usize) -> Self {
+        Traces {
+            hidden: vec![vec![vec![0.0; INPUTS + 1]; hidden]; OUTPUTS],
+            output: vec![vec![0.0; hidden + 1]; OUTPUTS],
+        }
+    }
+
+    // Decays the traces and adds the gradients of the outputs
+    fn add(&mut self, network: &Network, current: &Activations, lambda: f64) {
+        for k in 0..OUTPUTS {
+            let y = current.outputs[k];
+            let dy = y * (1.0 - y);
+            for j in 0..(network.hidden + 1) {
+                let h = current.hidden.get(j).cloned().unwrap_or(1.0);
+                let trace = &mut self.output[k][j];
+                *trace = lambda * *trace + dy * h;
+            }
+            for j in 0..network.hidden {
+                let h = current.hidden[j];
+                let dh = dy * network.output_weights[k][j] * h * (1.0 - h);
+                for i in 0..(INPUTS + 1) {
+                    let x = current.inputs.get(i).cloned().unwrap_or(1.0);
+                    let trace = &mut self.hidden[k][j][i];
+                    *trace = lambda * *trace + dh * x;
+                }
+            }
+        }
+    }
+}
+
+impl Network {
+    // Small random weights
+    pub fn new<R: Rng>(hidden: usize, rng: &mut R) -> Self {
+        let mut row = |n: usize| (0..n).map(|_| rng.gen_range(-0.1, 0.1)).collect::<Vec<f64>>();
+        Network {
+            hidden: hidden,
+            hidden_weights: (0..hidden).map(|_| row(INPUTS + 1)).collect::<Vec<_>>(),
+            output_weights: (0..OUTPUTS).map(|_| row(hidden + 1)).collect::<Vec<_>>(),
+        }
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    pub fn outcomes(&self, s: &Backgammon) -> Outcomes {
+        Outcomes::from_slice(&self.activate(s).outputs)
+    }
+
+    fn activate(&self, s: &Backgammon) -> Activations {
+        let inputs = inputs(s);
+        let hidden: Vec<f64> = self.hidden_weights
+            .iter()
+            .map(|row| sigmoid(weighted_sum(row, &inputs)))
+            .collect();
+        let outputs = self.output_weights
+            .iter()
+            .map(|row| sigmoid(weighted_sum(row, &hidden)))
+            .collect();
+        Activations {
+            inputs: inputs,
+            hidden: hidden,
+            outputs: outputs,
+        }
+    }
+
+    // Plays `games` cubeless games against itself, learning with TD(λ) after every move
+    pub fn train<R: Rng>(&mut self, games: usize, alpha: f64, lambda: f64, rng: &mut R) {
+        for _ in 0..games {
+            self.train_game(alpha, lambda, rng);
+        }
+    }
+
+    // Moves the outputs for the position toward the target,
+    // as a step of training does when it ignores the positions before
+    pub fn learn(&mut self, s: &Backgammon, target: Outcomes, alpha: f64) {
+        let mut traces = Traces::new(self.hidden);
+        let current = self.activate(s);
+        traces.add(self, &current, 0.0);
+        self.update(&traces, &current, &target.to_vec(), alpha);
+    }
+
+    fn train_game<R: Rng>(&mut self, alpha: f64, lambda: f64, rng: &mut R) {
+        let mut traces = Traces::new(self.hidden);
+        let mut s = Backgammon::new();
+        s.player = rng.gen();
+        let mut current = self.activate(&s);
+        while !s.finished() {
+            s.apply(Move::Roll(roll(rng)));
+            let m = self.best_move(&s).expect("Playing always has a legal move");
+            s.apply(m);
+            traces.add(self, &current, lambda);
+            let next = self.activate(&s);
+            // The final result is the target once the game is over
+            let target = Outcomes::of_result(&s).map_or_else(|| next.outputs.clone(), |o| o.to_vec());
+            self.update(&traces, &current, &target, alpha);
+            current = next;
+        }
+    }
+
+    fn update(&mut self, traces: &Traces, current: &Activations, target: &[f64], alpha: f64) {
+        for k in 0..OUTPUTS {
+            let error = alpha * (target[k] - current.outputs[k]);
+            for j in 0..(self.hidden + 1) {
+                self.output_weights[k][j] += error * traces.output[k][j];
+            }
+            for j in 0..self.hidden {
+                for i in 0..(INPUTS + 1) {
+                    self.hidden_weights[j][i] += error * traces.hidden[k][j][i];
+                }
+            }
+        }
+    }
+
+    // The play leading to the best position for the player, evaluated one move ahead
+    pub fn best_move(&self, s: &Backgammon) -> Option<Move> {
+        let p = s.player;
+        s.legal_moves()
+            .into_iter()
+            .map(|m| {
+                let mut new = s.clone();
+                new.apply(m.clone());
+                let outcomes = Outcomes::of_result(&new).unwrap_or_else(|| self.outcomes(&new));
+                let equity = if p { outcomes.equity() } else { -outcomes.equity() };
+                (m, equity)
+            })
+            .fold(None, |best: Option<(Move, f64)>, (m, e)| match best {
+                Some((_, b)) if b >= e => best,
+                _ => Some((m, e)),
+            })
+            .map(|(m, _)| m)
+    }
+
+    // Plain text format: the number of hidden units on the first line,
+    // then a row of weights per line, hidden units first
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#'));
+        let hidden: usize = lines
+            .next()
+            .ok_or("Missing number of hidden units")?
+            .parse()
+            .map_err(|_| "Bad number of hidden units")?;
+        let rows = lines
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|x| x.parse::<f64>().map_err(|e| format!("{}: {}", x, e)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if rows.len() != hidden + OUTPUTS {
+            Err(format!("Expected {} rows of weights, found {}", hidden + OUTPUTS, rows.len()))?;
+        }
+        let (hidden_weights, output_weights) = rows.split_at(hidden);
+        if hidden_weights.iter().any(|row| row.len() != INPUTS + 1) ||
+            output_weights.iter().any(|row| row.len() != hidden + 1)
+        {
+            Err("Rows of weights have the wrong length")?;
+        }
+        Ok(Network {
+            hidden: hidden,
+            hidden_weights: hidden_weights.to_vec(),
+            output_weights: output_weights.to_vec(),
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+        Self::parse(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut f = File::create(path)?;
+        writeln!(f, "# Hidden units, then weights of the hidden units and the outputs")?;
+        writeln!(f, "{}", self.hidden)?;
+        for row in self.hidden_weights.iter().chain(&self.output_weights) {
+            let row: Vec<_> = row.iter().map(|x| format!("{}", x)).collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+// Expected scores according to the network, ignoring the cube's value to whoever owns it
+impl Evaluator<Backgammon> for Network {
+    fn evaluate(&self, s: &Backgammon) -> Option<ScoreBoard<Backgammon>> {
+        if let Some(scores) = s.scores() {
+            return Some(scores);
+        }
+        let cube = s.cube.map_or(1, |c| c.value) as f64;
+        let white = cube * self.outcomes(s).equity();
+        let mut scores = ScoreBoard::<Backgammon>::new();
+        scores.insert(Some(true), white);
+        scores.insert(Some(false), -white);
+        scores.insert(None, 0.0);
+        Some(scores)
+    }
+}
+
+// The TD-Gammon encoding: for every point and player,
+// units for at least 1, 2 and 3 pieces and half of any pieces beyond 3,
+// then half the pieces on the bar, fifteenths of the pieces borne off
+// and a unit for each player saying whether it's their turn
+pub fn inputs(s: &Backgammon) -> Vec<f64> {
+    let mut v = Vec::with_capacity(INPUTS);
+    for p in &[true, false] {
+        let pieces = |i: usize| {
+            let count = s.counts[i];
+            (if *p { count.0 } else { count.1 }).0 as f64
+        };
+        for i in 1..25 {
+            let n = pieces(i);
+            v.push(if n >= 1.0 { 1.0 } else { 0.0 });
+            v.push(if n >= 2.0 { 1.0 } else { 0.0 });
+            v.push(if n >= 3.0 { 1.0 } else { 0.0 });
+            v.push(if n > 3.0 { (n - 3.0) / 2.0 } else { 0.0 });
+        }
+        v.push(pieces(usize::from(Bar)) / 2.0);
+        v.push(pieces(usize::from(Home)) / 15.0);
+    }
+    v.push(if s.player { 1.0 } else { 0.0 });
+    v.push(if s.player { 0.0 } else { 1.0 });
+    v
+}
+
+fn roll<R: Rng>(rng: &mut R) -> (u8, u8) {
+    let (x, y) = (rng.gen_range(1, 7), rng.gen_range(1, 7));
+    (x.max(y), x.min(y))
+}
+
+fn weighted_sum(row: &[f64], inputs: &[f64]) -> f64 {
+    let (bias, weights) = row.split_last().unwrap();
+    weights.iter().zip(inputs).map(|(w, x)| w * x).sum::<f64>() + bias
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
@@ -0,0 +1,289 @@
+// Standard backgammon notation, such as "24/18 13/11", "bar/22*", "6/off" and "8/4(2)",
+// with points counted from the side of the player making the move
+use game::{Codec, GameState, Notation};
+use super::{travelled, Backgammon, CubeAction, Move, Phase, Point, SingleMove};
+use super::Location::{self, Bar, Board, Home};
+
+// A piece going from one place to another, possibly hitting on arrival
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Segment {
+    pub from: Location,
+    pub to: Location,
+    pub hit: bool,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Style {
+    // "13/7*/5 8/4(2) bar/22 6/off"
+    Standard,
+    // One segment at a time with the bar as 25 and home as 0, as in .mat files
+    Numbers,
+}
+
+// Reads any move the position allows, including rolls such as "65" or "6-5"
+// and the cube actions "double", "no double" or "roll", "take" and "drop".
+// Pieces moves resolve to the legal move with the described result,
+// so they can be written in any order or grouping
+pub fn parse_move(s: &Backgammon, text: &str) -> Result<Move, String> {
+    let text = text.trim().to_lowercase();
+    let m = match text.as_str() {
+        "double" => Move::Cube(CubeAction::Double),
+        "no double" => Move::Cube(CubeAction::NoDouble),
+        "roll" if s.phase == Phase::Cube => Move::Cube(CubeAction::NoDouble),
+        "take" => Move::Cube(CubeAction::Take),
+        "drop" | "pass" => Move::Cube(CubeAction::Drop),
+        _ if s.phase == Phase::Roll => Move::Roll(parse_roll(&text)?),
+        "no move" | "" => resolve(s, &[])?,
+        _ => resolve(s, &parse_segments(&text, s.player)?)?,
+    };
+    if !s.legal_moves().contains(&m) {
+        Err(format!("{} isn't a legal move", text))?;
+    }
+    Ok(m)
+}
+
+pub fn format_move(s: &Backgammon, m: &Move) -> String {
+    match *m {
+        Move::Roll((x, y)) => format!("{}{}", x, y),
+        Move::Play(ref ms) if ms.is_empty() => "no move".to_string(),
+        Move::Play(ref ms) => format_segments(&segments(s, ms), s.player, Style::Standard),
+        Move::Cube(CubeAction::NoDouble) => "no double".to_string(),
+        Move::Cube(CubeAction::Double) => "double".to_string(),
+        Move::Cube(CubeAction::Take) => "take".to_string(),
+        Move::Cube(CubeAction::Drop) => "drop".to_string(),
+    }
+}
+
+impl Notation for Backgammon {
+    fn parse_move(&self, text: &str) -> Result<Move, String> {
+        parse_move(self, text)
+    }
+
+    fn format_move(&self, m: &Move) -> String {
+        format_move(self, m)
+    }
+}
+
+// GNU Backgammon IDs, eXtreme Gammon IDs are read as well
+impl Codec for Backgammon {
+    fn parse_position(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.starts_with("XGID=") {
+            Backgammon::from_xgid(text)
+        } else {
+            Backgammon::from_gnubg_id(text)
+        }
+    }
+
+    fn format_position(&self) -> String {
+        self.gnubg_id()
+    }
+
+    // Not doubling is implied by rolling
+    fn set_dice(&mut self, text: &str) -> Result<(), String> {
+        let roll = parse_roll(text)?;
+        if self.phase == Phase::Cube {
+            self.apply(Move::Cube(CubeAction::NoDouble));
+        }
+        if self.phase != Phase::Roll {
+            Err("It isn't time to roll")?;
+        }
+        self.apply(Move::Roll(roll));
+        Ok(())
+    }
+}
+
+// Two dice, highest first
+pub fn parse_roll(text: &str) -> Result<(u8, u8), String> {
+    let dice: Vec<u8> = text.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| match c.to_digit(10) {
+            Some(n) if n >= 1 && n <= 6 => Ok(n as u8),
+            _ => Err(format!("{} isn't a die", c)),
+        })
+        .collect::<Result<_, _>>()?;
+    if dice.len() != 2 {
+        Err(format!("Expected two dice, found {}", text))?;
+    }
+    Ok((dice[0].max(dice[1]), dice[0].min(dice[1])))
+}
+
+// Also accepts "25" for the bar and "0" for home,
+// "24/18/13" for a piece moving twice and "13/11(2)" for several pieces moving alike
+pub fn parse_segments(text: &str, player: bool) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    for word in text.split_whitespace() {
+        let (word, repeat) = match word.find('(') {
+            Some(i) => {
+                let n = word[(i + 1)..]
+                    .trim_end_matches(')')
+                    .parse()
+                    .map_err(|_| format!("Bad repetition in {}", word))?;
+                (&word[..i], n)
+            }
+            None => (word, 1),
+        };
+        let places: Vec<_> = word.split('/').collect();
+        if places.len() < 2 {
+            Err(format!("Expected from/to, found {}", word))?;
+        }
+        for _ in 0..repeat {
+            for pair in places.windows(2) {
+                segments.push(Segment {
+                    from: parse_location(pair[0], player)?,
+                    to: parse_location(pair[1], player)?,
+                    hit: pair[1].ends_with('*'),
+                });
+            }
+        }
+    }
+    Ok(segments)
+}
+
+fn parse_location(s: &str, player: bool) -> Result<Location, String> {
+    Ok(match s.trim_end_matches('*') {
+        "bar" | "25" => Bar,
+        "off" | "0" => Home,
+        n => {
+            let n: u8 = n.parse().map_err(|_| format!("Bad point {}", n))?;
+            if n > 25 {
+                Err(format!("Bad point {}", n))?;
+            }
+            Board(Point(if player { 25 - n } else { n }))
+        }
+    })
+}
+
+fn format_location(l: Location, player: bool, style: Style) -> String {
+    match (l, style) {
+        (Bar, Style::Standard) => "bar".to_string(),
+        (Bar, Style::Numbers) => "25".to_string(),
+        (Home, Style::Standard) => "off".to_string(),
+        (Home, Style::Numbers) => "0".to_string(),
+        (Board(Point(n)), _) => format!("{}", if player { 25 - n } else { n }),
+    }
+}
+
+pub fn format_segments(segments: &[Segment], player: bool, style: Style) -> String {
+    // Each path is where a piece went and whether it hit on arrival
+    let mut paths: Vec<Vec<(Location, bool)>> = segments
+        .iter()
+        .map(|seg| vec![(seg.from, false), (seg.to, seg.hit)])
+        .collect();
+    if style == Style::Standard {
+        // Join up pieces moving more than once
+        let mut i = 0;
+        while i < paths.len() {
+            let end = paths[i].last().unwrap().0;
+            let next = (0..paths.len()).find(|&j| j != i && end != Home && paths[j][0].0 == end);
+            match next {
+                Some(j) => {
+                    let rest = paths.remove(j);
+                    let i = if j < i { i - 1 } else { i };
+                    paths[i].extend(rest.into_iter().skip(1));
+                }
+                None => i += 1,
+            }
+        }
+        paths.sort_by_key(|path| travelled(path[0].0, player));
+    }
+    let texts: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            path.iter()
+                .map(|&(l, hit)| format!("{}{}", format_location(l, player, style), if hit { "*" } else { "" }))
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect();
+    if style == Style::Numbers {
+        return texts.join(" ");
+    }
+    let mut grouped: Vec<(String, usize)> = Vec::new();
+    for text in texts {
+        let repeated = grouped.last().map_or(false, |&(ref last, _)| *last == text);
+        if repeated {
+            grouped.last_mut().unwrap().1 += 1;
+        } else {
+            grouped.push((text, 1));
+        }
+    }
+    grouped
+        .into_iter()
+        .map(|(text, n)| if n > 1 { format!("{}({})", text, n) } else { text })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The single moves in the order they are made, with the hits they make
+pub fn segments(s: &Backgammon, ms: &[SingleMove]) -> Vec<Segment> {
+    let p = s.player;
+    let mut ms = ms.to_vec();
+    ms.sort_by_key(|&(l, _)| travelled(l, p));
+    let mut s = s.clone();
+    let mut segments = Vec::new();
+    for m in ms {
+        let before = s.pieces(Bar, !p);
+        s.apply(Move::Play(vec![m]));
+        // Undo the change of turn, only the pieces matter here
+        s.player = p;
+        let distance = travelled(m.0, p) + m.1;
+        let to = if distance >= 25 {
+            Home
+        } else {
+            Board(Point(if p { distance } else { 25 - distance }))
+        };
+        segments.push(Segment {
+            from: m.0,
+            to: to,
+            hit: s.pieces(Bar, !p) > before,
+        });
+    }
+    segments
+}
+
+// Finds the legal move with the result the segments describe
+pub fn resolve(s: &Backgammon, segments: &[Segment]) -> Result<Move, String> {
+    let p = s.player;
+    let mut expected = (layout(s, p), layout(s, !p));
+    for segment in segments {
+        let (from, to): (usize, usize) = (segment.from.into(), segment.to.into());
+        expected.0[from] -= 1;
+        expected.0[to] += 1;
+        if segment.hit && expected.1[to] > 0 {
+            expected.1[to] -= 1;
+            expected.1[usize::from(Bar)] += 1;
+        }
+    }
+    let results: Vec<_> = s.legal_moves()
+        .into_iter()
+        .filter(|m| match *m {
+            Move::Play(_) => true,
+            _ => false,
+        })
+        .map(|m| {
+            let mut new = s.clone();
+            new.apply(m.clone());
+            (m, layout(&new, p), layout(&new, !p))
+        })
+        .collect();
+    // Hits are sometimes left unmarked, so fall back on the player's own pieces
+    results
+        .iter()
+        .find(|&&(_, ref own, ref other)| *own == expected.0 && *other == expected.1)
+        .or_else(|| results.iter().find(|&&(_, ref own, _)| *own == expected.0))
+        .map(|&(ref m, _, _)| m.clone())
+        .ok_or_else(|| {
+            format!(
+                "No legal move matches {}",
+                format_segments(segments, p, Style::Standard)
+            )
+        })
+}
+
+fn layout(s: &Backgammon, p: bool) -> Vec<i32> {
+    s.counts
+        .iter()
+        .map(|&(w, b)| if p { w.0 as i32 } else { b.0 as i32 })
+        .collect()
+}
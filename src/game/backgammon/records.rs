@@ -0,0 +1,559 @@
+// Game records in the Jellyfish/GNU Backgammon .mat format and SGF (GM[6]).
+// White is the left player of a .mat file and W in SGF, black the right player and B
+use game::GameState;
+use super::{Backgammon, CubeAction, Move, Phase, Point, Roll};
+use super::Location::{self, Bar, Board, Home};
+use super::notation::{self, format_segments, parse_segments, resolve, Segment, Style};
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+// A game as a starting position and every move from there, dice included
+#[derive(PartialEq, Clone, Debug)]
+pub struct GameRecord {
+    pub start: Backgammon,
+    pub moves: Vec<Move>,
+    // Points of (white, black) before the game
+    pub score: (u32, u32),
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct MatchRecord {
+    // 0 for money games
+    pub length: u32,
+    // Names of (white, black)
+    pub players: (String, String),
+    pub games: Vec<GameRecord>,
+}
+
+impl GameRecord {
+    pub fn new(start: Backgammon) -> Self {
+        GameRecord {
+            start: start,
+            moves: Vec::new(),
+            score: (0, 0),
+        }
+    }
+
+    // The state before each move, followed by the final state
+    pub fn states(&self) -> Vec<Backgammon> {
+        let mut s = self.start.clone();
+        let mut v = vec![s.clone()];
+        for m in &self.moves {
+            s.apply(m.clone());
+            v.push(s.clone());
+        }
+        v
+    }
+
+    pub fn end(&self) -> Backgammon {
+        let mut s = self.start.clone();
+        for m in &self.moves {
+            s.apply(m.clone());
+        }
+        s
+    }
+
+    // Adds a move made by the player, implicitly declining to double
+    // if the record skips straight to the roll
+    fn push(&mut self, s: &mut Backgammon, player: bool, m: Move) -> Result<(), String> {
+        if self.moves.is_empty() && s.current_player().is_none() {
+            s.player = player;
+            self.start.player = player;
+        }
+        if s.phase == Phase::Cube && m != Move::Cube(CubeAction::Double) {
+            self.push_legal(s, Move::Cube(CubeAction::NoDouble))?;
+        }
+        let mover = match m {
+            Move::Cube(CubeAction::Take) |
+            Move::Cube(CubeAction::Drop) => !s.player,
+            _ => s.player,
+        };
+        if mover != player {
+            Err(format!("It's not {}'s turn", if player { "white" } else { "black" }))?;
+        }
+        self.push_legal(s, m)
+    }
+
+    fn push_legal(&mut self, s: &mut Backgammon, m: Move) -> Result<(), String> {
+        if !s.legal_moves().contains(&m) {
+            Err(format!("Illegal move {:?} in position {}", m, s.gnubg_id()))?;
+        }
+        s.apply(m.clone());
+        self.moves.push(m);
+        Ok(())
+    }
+
+    // What happened each turn, paired with the player
+    fn turns(&self) -> Vec<(bool, Turn)> {
+        let mut v = Vec::new();
+        let mut s = self.start.clone();
+        for m in &self.moves {
+            match *m {
+                Move::Roll(roll) => v.push((s.player, Turn::Roll(roll, Vec::new()))),
+                Move::Play(ref ms) => {
+                    if let Some(&mut (_, Turn::Roll(_, ref mut segments))) = v.last_mut() {
+                        *segments = notation::segments(&s, ms);
+                    }
+                }
+                Move::Cube(CubeAction::NoDouble) => {}
+                Move::Cube(CubeAction::Double) => {
+                    let value = s.cube.map_or(1, |c| c.value);
+                    v.push((s.player, Turn::Double(2 * value)))
+                }
+                Move::Cube(CubeAction::Take) => v.push((!s.player, Turn::Take)),
+                Move::Cube(CubeAction::Drop) => v.push((!s.player, Turn::Drop)),
+            }
+            s.apply(m.clone());
+        }
+        if let Some(scores) = s.scores() {
+            let white = scores[&Some(true)];
+            v.push((white > 0.0, Turn::Win(white.abs() as u32)));
+        }
+        v
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Turn {
+    Roll(Roll, Vec<Segment>),
+    Double(u32),
+    Take,
+    Drop,
+    Win(u32),
+}
+
+impl MatchRecord {
+    pub fn new(length: u32, white: &str, black: &str) -> Self {
+        MatchRecord {
+            length: length,
+            players: (white.to_string(), black.to_string()),
+            games: Vec::new(),
+        }
+    }
+
+    pub fn parse_mat(text: &str) -> Result<Self, String> {
+        let mut record = MatchRecord::new(0, "", "");
+        let mut game: Option<(GameRecord, Backgammon)> = None;
+        let mut crawford = CrawfordRule::new();
+        // Where the right hand column starts, going by the header and lines with both columns
+        let mut right_column = None;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with(';') {
+                continue;
+            }
+            let words: Vec<_> = trimmed.split_whitespace().collect();
+            if words.len() == 3 && words[1] == "point" && words[2] == "match" {
+                record.length = words[0].parse().map_err(|_| format!("Bad match length in {}", trimmed))?;
+            } else if words.len() == 2 && words[0] == "Game" {
+                if let Some((g, _)) = game.take() {
+                    record.games.push(g);
+                }
+            } else if trimmed.contains(" : ") && game.is_none() && !trimmed.contains(')') {
+                let (white, score, black) = parse_players(trimmed)?;
+                let end = line.rfind(" : ").unwrap_or(0);
+                right_column = Some(column(line, end).saturating_sub(black.chars().count()));
+                record.players = (white, black);
+                let start = crawford.next_game(record.length, score);
+                let mut g = GameRecord::new(start.clone());
+                g.score = score;
+                game = Some((g, start));
+            } else if words[0] == "Wins" {
+                // GNU Backgammon writes the result on a line of its own
+                game.as_ref().ok_or("Moves before a game header")?;
+            } else if let Some(i) = line.find(')') {
+                let &mut (ref mut g, ref mut s) = game.as_mut().ok_or("Moves before a game header")?;
+                let entries = split_entries(line, i + 1);
+                if entries.len() == 2 {
+                    right_column = Some(entries[1].0);
+                }
+                // A lone entry belongs to the column it is nearer to
+                let left_column = column(line, i + 1);
+                let players: Vec<bool> = if entries.len() == 2 {
+                    vec![true, false]
+                } else {
+                    entries
+                        .iter()
+                        .map(|&(c, _)| right_column.map_or(true, |right| c - left_column < right.saturating_sub(c)))
+                        .collect()
+                };
+                for (&(_, ref entry), &player) in entries.iter().zip(&players) {
+                    apply_entry(g, s, player, entry)?;
+                }
+            } else {
+                Err(format!("Unexpected line {}", trimmed))?;
+            }
+        }
+        if let Some((g, _)) = game {
+            record.games.push(g);
+        }
+        Ok(record)
+    }
+
+    pub fn to_mat(&self) -> String {
+        let mut out = format!(" {} point match\n", self.length);
+        for (i, g) in self.games.iter().enumerate() {
+            out.push_str(&format!("\n Game {}\n", i + 1));
+            let left = format!("{} : {}", self.players.0, g.score.0);
+            out.push_str(&format!(" {:<33}{} : {}\n", left, self.players.1, g.score.1));
+            let mut lines: Vec<(Option<String>, Option<String>)> = Vec::new();
+            for (player, turn) in g.turns() {
+                let text = format_turn(&turn, player);
+                let new_line = match lines.last() {
+                    _ if player => true,
+                    Some(&(_, None)) => false,
+                    _ => true,
+                };
+                if new_line {
+                    lines.push((None, None));
+                }
+                let line = lines.last_mut().unwrap();
+                if player {
+                    line.0 = Some(text)
+                } else {
+                    line.1 = Some(text)
+                }
+            }
+            for (n, (left, right)) in lines.into_iter().enumerate() {
+                let left = left.unwrap_or_default();
+                let right = right.unwrap_or_default();
+                let line = format!("{:3}) {:<28} {}", n + 1, left, right);
+                out.push_str(line.trim_end());
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    // Only the main line of each game is read, variations are skipped
+    pub fn parse_sgf(text: &str) -> Result<Self, String> {
+        let mut record = MatchRecord::new(0, "", "");
+        let mut crawford = CrawfordRule::new();
+        for tree in parse_sgf_trees(text)? {
+            let root = tree.first().ok_or("Empty SGF game")?;
+            if property(root, "GM").map_or(false, |gm| gm != "6") {
+                Err("Not a backgammon SGF")?;
+            }
+            let mut score = (0, 0);
+            for value in properties(root, "MI") {
+                let mut kv = value.splitn(2, ':');
+                let (key, value) = (kv.next().unwrap_or(""), kv.next().unwrap_or(""));
+                let number = || value.parse::<u32>().map_err(|_| format!("Bad number in MI[{}]", value));
+                match key {
+                    "length" => record.length = number()?,
+                    "ws" => score.0 = number()?,
+                    "bs" => score.1 = number()?,
+                    _ => {}
+                }
+            }
+            if let Some(name) = property(root, "PW") {
+                record.players.0 = name.to_string();
+            }
+            if let Some(name) = property(root, "PB") {
+                record.players.1 = name.to_string();
+            }
+            let mut s = crawford.next_game(record.length, score);
+            let mut g = GameRecord::new(s.clone());
+            g.score = score;
+            for node in &tree[1..] {
+                for &(player, key) in &[(true, "W"), (false, "B")] {
+                    if let Some(value) = property(node, key) {
+                        apply_sgf_move(&mut g, &mut s, player, value)?;
+                    }
+                }
+            }
+            record.games.push(g);
+        }
+        Ok(record)
+    }
+
+    pub fn to_sgf(&self) -> String {
+        let mut out = String::new();
+        for (i, g) in self.games.iter().enumerate() {
+            out.push_str(&format!(
+                "(;FF[4]GM[6]CA[UTF-8]AP[game-trees]MI[length:{}][game:{}][ws:{}][bs:{}]PW[{}]PB[{}]",
+                self.length,
+                i,
+                g.score.0,
+                g.score.1,
+                escape_sgf(&self.players.0),
+                escape_sgf(&self.players.1)
+            ));
+            let end = g.end();
+            if let Some(scores) = end.scores() {
+                let white = scores[&Some(true)];
+                out.push_str(&format!("RE[{}+{}]", if white > 0.0 { "W" } else { "B" }, white.abs()));
+            }
+            for (player, turn) in g.turns() {
+                let value = match turn {
+                    Turn::Roll((x, y), segments) => {
+                        let mut v = format!("{}{}", x, y);
+                        for segment in segments {
+                            v.push(sgf_point(segment.from));
+                            v.push(sgf_point(segment.to));
+                        }
+                        v
+                    }
+                    Turn::Double(_) => "double".to_string(),
+                    Turn::Take => "take".to_string(),
+                    Turn::Drop => "drop".to_string(),
+                    Turn::Win(_) => continue,
+                };
+                out.push_str(&format!("\n;{}[{}]", if player { "W" } else { "B" }, value));
+            }
+            out.push_str(")\n");
+        }
+        out
+    }
+
+    // The format is chosen by the extension, SGF for .sgf and .mat otherwise
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut text = String::new();
+        File::open(&path)?.read_to_string(&mut text)?;
+        let record = if is_sgf(path.as_ref()) {
+            Self::parse_sgf(&text)
+        } else {
+            Self::parse_mat(&text)
+        };
+        record.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let text = if is_sgf(path.as_ref()) {
+            self.to_sgf()
+        } else {
+            self.to_mat()
+        };
+        File::create(path)?.write_all(text.as_bytes())
+    }
+}
+
+fn is_sgf(path: &Path) -> bool {
+    path.extension().map_or(false, |e| e == "sgf")
+}
+
+// Keeps track of the Crawford rule from game to game,
+// since the records don't say which game it applies to
+struct CrawfordRule {
+    played: bool,
+}
+
+impl CrawfordRule {
+    fn new() -> Self {
+        CrawfordRule { played: false }
+    }
+
+    fn next_game(&mut self, length: u32, score: (u32, u32)) -> Backgammon {
+        let one_away = length > 0 && (score.0 + 1 == length || score.1 + 1 == length);
+        if one_away && !self.played {
+            self.played = true;
+            Backgammon::new()
+        } else {
+            Backgammon::with_cube()
+        }
+    }
+}
+
+fn parse_players(line: &str) -> Result<(String, (u32, u32), String), String> {
+    let parts: Vec<_> = line.split(" : ").collect();
+    let error = || format!("Expected \"name : score  name : score\", found {}", line);
+    if parts.len() != 3 {
+        Err(error())?;
+    }
+    let mut middle = parts[1].trim().splitn(2, ' ');
+    let white_score = middle.next().and_then(|n| n.parse().ok()).ok_or_else(&error)?;
+    let black = middle.next().ok_or_else(&error)?.trim().to_string();
+    let black_score = parts[2].trim().parse().map_err(|_| error())?;
+    Ok((parts[0].trim().to_string(), (white_score, black_score), black))
+}
+
+// Splits the line from the byte `start` on into the moves of each column,
+// with the columns they start at
+fn split_entries(line: &str, start: usize) -> Vec<(usize, String)> {
+    let mut entries: Vec<(usize, String)> = Vec::new();
+    let mut offset = start;
+    for word in line[start..].split_whitespace() {
+        offset += line[offset..].find(word).unwrap_or(0);
+        let c = column(line, offset);
+        offset += word.len();
+        let starts_entry = is_roll(word) || ["Doubles", "Takes", "Drops", "Wins", "Beavers"].contains(&word);
+        match entries.last_mut() {
+            Some(&mut (_, ref mut entry)) if !starts_entry => {
+                entry.push(' ');
+                entry.push_str(word);
+                continue;
+            }
+            _ => {}
+        }
+        entries.push((c, word.to_string()));
+    }
+    entries
+}
+
+// Characters before the byte `i` of the line
+fn column(line: &str, i: usize) -> usize {
+    line[..i].chars().count()
+}
+
+fn is_roll(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 3 && bytes[2] == b':' && bytes[..2].iter().all(|&b| b >= b'1' && b <= b'6')
+}
+
+fn apply_entry(g: &mut GameRecord, s: &mut Backgammon, player: bool, entry: &str) -> Result<(), String> {
+    let words: Vec<_> = entry.split_whitespace().collect();
+    match words[0] {
+        "Doubles" => g.push(s, player, Move::Cube(CubeAction::Double)),
+        "Takes" => g.push(s, player, Move::Cube(CubeAction::Take)),
+        "Drops" => g.push(s, player, Move::Cube(CubeAction::Drop)),
+        "Wins" => Ok(()),
+        "Beavers" => Err("Beavers aren't supported".to_string()),
+        roll => {
+            if !is_roll(roll) {
+                Err(format!("Expected a roll such as 52:, found {}", roll))?;
+            }
+            let bytes = roll.as_bytes();
+            let (x, y) = (bytes[0] - b'0', bytes[1] - b'0');
+            g.push(s, player, Move::Roll((x.max(y), x.min(y))))?;
+            let segments = parse_segments(&words[1..].join(" "), player)?;
+            let m = resolve(s, &segments)?;
+            g.push(s, player, m)
+        }
+    }
+}
+
+fn apply_sgf_move(g: &mut GameRecord, s: &mut Backgammon, player: bool, value: &str) -> Result<(), String> {
+    match value {
+        "double" => g.push(s, player, Move::Cube(CubeAction::Double)),
+        "take" => g.push(s, player, Move::Cube(CubeAction::Take)),
+        "drop" => g.push(s, player, Move::Cube(CubeAction::Drop)),
+        _ => {
+            let bytes = value.as_bytes();
+            if bytes.len() < 2 || bytes.len() % 2 != 0 {
+                Err(format!("Bad SGF move {}", value))?;
+            }
+            let die = |b: u8| if b >= b'1' && b <= b'6' {
+                Ok(b - b'0')
+            } else {
+                Err(format!("Bad die in SGF move {}", value))
+            };
+            let (x, y) = (die(bytes[0])?, die(bytes[1])?);
+            g.push(s, player, Move::Roll((x.max(y), x.min(y))))?;
+            let mut segments = Vec::new();
+            for pair in bytes[2..].chunks(2) {
+                let from = sgf_location(pair[0], player)?;
+                let to = sgf_location(pair[1], player)?;
+                let hit = to != Home && s.any_loc(to, !player);
+                segments.push(Segment {
+                    from: from,
+                    to: to,
+                    hit: hit,
+                });
+            }
+            let m = resolve(s, &segments)?;
+            g.push(s, player, m)
+        }
+    }
+}
+
+fn format_turn(turn: &Turn, player: bool) -> String {
+    match *turn {
+        Turn::Roll((x, y), ref segments) => {
+            format!("{}{}: {}", x, y, format_segments(segments, player, Style::Numbers))
+        }
+        Turn::Double(value) => format!(" Doubles => {}", value),
+        Turn::Take => " Takes".to_string(),
+        Turn::Drop => " Drops".to_string(),
+        Turn::Win(1) => " Wins 1 point".to_string(),
+        Turn::Win(points) => format!(" Wins {} points", points),
+    }
+}
+
+type SgfNode = Vec<(String, Vec<String>)>;
+
+fn parse_sgf_trees(text: &str) -> Result<Vec<Vec<SgfNode>>, String> {
+    let mut trees = Vec::new();
+    let mut depth = 0;
+    // The main line goes on into the first variation of each node,
+    // so it ends where the first variation does
+    let mut main = false;
+    let mut chars = text.chars().peekable();
+    let mut id = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => {
+                depth += 1;
+                if depth == 1 {
+                    trees.push(Vec::new());
+                    main = true;
+                }
+            }
+            ')' => {
+                if depth == 0 {
+                    Err("Unbalanced parentheses in SGF")?;
+                }
+                depth -= 1;
+                main = false;
+            }
+            ';' if main => trees.last_mut().unwrap().push(Vec::new()),
+            '[' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next().ok_or("Unterminated SGF value")? {
+                        '\\' => value.push(chars.next().ok_or("Unterminated SGF value")?),
+                        ']' => break,
+                        c => value.push(c),
+                    }
+                }
+                if main {
+                    let node = trees
+                        .last_mut()
+                        .and_then(|t| t.last_mut())
+                        .ok_or("SGF value outside a node")?;
+                    if !id.is_empty() {
+                        node.push((id.clone(), Vec::new()));
+                        id.clear();
+                    }
+                    node.last_mut().ok_or("SGF value without a property")?.1.push(value);
+                }
+            }
+            c if main && c >= 'A' && c <= 'Z' => id.push(c),
+            _ => {}
+        }
+    }
+    Ok(trees)
+}
+
+fn properties<'a>(node: &'a SgfNode, key: &str) -> Vec<&'a str> {
+    node.iter()
+        .filter(|&&(ref k, _)| k == key)
+        .flat_map(|&(_, ref values)| values.iter().map(|v| v.as_str()))
+        .collect()
+}
+
+fn property<'a>(node: &'a SgfNode, key: &str) -> Option<&'a str> {
+    properties(node, key).into_iter().next()
+}
+
+fn escape_sgf(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+fn sgf_point(l: Location) -> char {
+    match l {
+        Bar => 'y',
+        Home => 'z',
+        Board(Point(n)) => (b'a' + n - 1) as char,
+    }
+}
+
+fn sgf_location(c: u8, player: bool) -> Result<Location, String> {
+    Ok(match c {
+        b'y' => Bar,
+        b'z' => Home,
+        c if c >= b'a' && c <= b'x' => Board(Point(c - b'a' + 1)),
+        c => Err(format!("Bad SGF point {} for {}", c as char, if player { "white" } else { "black" }))?,
+    })
+}
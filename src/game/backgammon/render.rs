@@ -0,0 +1,176 @@
+// Diagrams of the board as seen by the player whose turn it is,
+// with their home board at the bottom right and the points numbered their way.
+// White's pieces are O and black's X, as in eXtreme Gammon
+use game::Score;
+use play::Render;
+use super::{Backgammon, Phase, Player};
+use super::Location::{Bar, Home};
+
+// Pieces shown on a point before the rest are counted instead
+const STACK: usize = 5;
+
+const WHITE: &'static str = "\x1b[1;37m";
+const BLACK: &'static str = "\x1b[1;31m";
+const RESET: &'static str = "\x1b[0m";
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct BoardStyle {
+    // Round pieces and box drawing lines rather than letters and dashes
+    pub unicode: bool,
+    // ANSI colours for the pieces
+    pub colour: bool,
+}
+
+impl BoardStyle {
+    fn piece(&self, p: bool) -> &'static str {
+        match (self.unicode, p) {
+            (false, true) => "O",
+            (false, false) => "X",
+            (true, true) => "○",
+            (true, false) => "●",
+        }
+    }
+
+    fn paint(&self, p: bool, text: &str) -> String {
+        if self.colour {
+            format!("{}{}{}", if p { WHITE } else { BLACK }, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    // What is shown of n of the player's pieces at a height, counting from the edge of the board
+    fn stack(&self, p: bool, n: usize, height: usize) -> String {
+        if n <= height {
+            "   ".to_string()
+        } else if height == STACK - 1 && n > STACK {
+            format!("{} ", self.paint(p, &format!("{:>2}", n)))
+        } else {
+            format!(" {} ", self.paint(p, self.piece(p)))
+        }
+    }
+
+    // The edge of the board with the point numbers in it
+    fn border(&self, numbers: &[u8], top: bool) -> String {
+        let (left, right, line) = match (self.unicode, top) {
+            (false, _) => ("+", "+", "-"),
+            (true, true) => ("┌", "┐", "─"),
+            (true, false) => ("└", "┘", "─"),
+        };
+        let half = |ns: &[u8]| ns.iter().map(|n| format!("{:>2}{}", n, line)).collect::<String>();
+        format!(
+            "{}{}{}{}{}",
+            left,
+            half(&numbers[..6]),
+            line.repeat(5),
+            half(&numbers[6..]),
+            right
+        )
+    }
+}
+
+impl Backgammon {
+    pub fn diagram(&self, style: BoardStyle) -> String {
+        let me = self.player;
+        // The point with the player's number n is n pips from home
+        let point = |n: u8| if me { 25 - n } else { n } as usize;
+        let count = |i: usize, p: bool| {
+            let c = self.counts[i];
+            (if p { c.0 } else { c.1 }).0 as usize
+        };
+        let cell = |i: usize, height: usize| {
+            let (white, black) = (count(i, true), count(i, false));
+            if white > 0 {
+                style.stack(true, white, height)
+            } else {
+                style.stack(false, black, height)
+            }
+        };
+        // Pieces on the bar are stacked in the middle, each player's on their own side
+        let bar = |p: bool, height: usize| style.stack(p, count(usize::from(Bar), p), height);
+        let edge = if style.unicode { "│" } else { "|" };
+        let row = |points: &[u8], height: usize, bar_owner: bool| {
+            let half = |ns: &[u8]| ns.iter().map(|&n| cell(point(n), height)).collect::<String>();
+            format!(
+                "{}{}{}{}{}{}{}",
+                edge,
+                half(&points[..6]),
+                edge,
+                bar(bar_owner, height),
+                edge,
+                half(&points[6..]),
+                edge
+            )
+        };
+        let top: Vec<u8> = (13..25).collect();
+        let bottom: Vec<u8> = (1..13).rev().collect();
+        let off = |p: bool| {
+            let n = count(usize::from(Home), p);
+            format!("  {} off: {}", style.paint(p, style.piece(p)), n)
+        };
+        let mut lines = vec![style.border(&top, true)];
+        for height in 0..STACK {
+            let mut line = row(&top, height, !me);
+            if height == 0 {
+                line += &off(!me);
+            }
+            lines.push(line);
+        }
+        lines.push(format!("{0}{1}{0}BAR{0}{1}{0}", edge, " ".repeat(18)));
+        for height in (0..STACK).rev() {
+            let mut line = row(&bottom, height, me);
+            if height == 0 {
+                line += &off(me);
+            }
+            lines.push(line);
+        }
+        lines.push(style.border(&bottom, false));
+        let name = |p: bool| Backgammon::player_name(&Some(p));
+        let (white, black) = self.pip_counts();
+        lines.push(format!(
+            "{} {} {} pips, {} {} {} pips",
+            style.paint(true, style.piece(true)),
+            name(true),
+            white,
+            style.paint(false, style.piece(false)),
+            name(false),
+            black
+        ));
+        if let Some(cube) = self.cube {
+            lines.push(match cube.owner {
+                None => format!("The cube is at {} in the middle", cube.value),
+                Some(p) => format!("The cube is at {} owned by {}", cube.value, name(p).to_lowercase()),
+            });
+        }
+        lines.push(match self.phase {
+            Phase::Play => format!("{} to play {}-{}", name(me), self.dice.0, self.dice.1),
+            Phase::Cube => format!("{} may double or roll", name(me)),
+            Phase::Roll => format!("{} to roll", name(me)),
+            Phase::Take => format!("{} to take or drop", name(!me)),
+            Phase::Dropped => format!("{} dropped", name(!me)),
+        });
+        lines.join("\n")
+    }
+}
+
+impl Render for Backgammon {
+    fn render(&self) -> String {
+        self.diagram(BoardStyle::default())
+    }
+
+    fn player_name(p: &Player) -> String {
+        match *p {
+            Some(true) => "White",
+            Some(false) => "Black",
+            None => "The dice",
+        }.to_string()
+    }
+
+    fn move_help() -> String {
+        "Write moves such as 24/18 13/11, bar/22* or 6/off, or one of double, no double, take and drop".to_string()
+    }
+
+    fn winnings(score: Score) -> Option<String> {
+        Some(if score == 1.0 { "1 point".to_string() } else { format!("{} points", score) })
+    }
+}
@@ -0,0 +1,237 @@
+// Reviewing a finished game: how much each decision cost compared to the best one
+// and how lucky each roll was, both as judged by an evaluator.
+// Cube decisions are judged as if the cube were dead after them
+use game::{Evaluator, GameState};
+use super::{Backgammon, Move};
+use super::notation;
+use super::records::GameRecord;
+use super::rollout::{roll_luck, value};
+use std::fmt;
+
+// Errors of at least these many points
+const DOUBTFUL: f64 = 0.04;
+const ERROR: f64 = 0.08;
+const BLUNDER: f64 = 0.16;
+// Average error per decision is scaled by this to give a performance rating
+const RATING_SCALE: f64 = 500.0;
+
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum Quality {
+    Good,
+    Doubtful,
+    Error,
+    Blunder,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Decision {
+    // Index of the move in the record
+    pub index: usize,
+    pub player: bool,
+    pub position: Backgammon,
+    pub played: Move,
+    pub best: Move,
+    // Expected points for the player after the played and the best move
+    pub equity: f64,
+    pub best_equity: f64,
+    pub quality: Quality,
+}
+
+impl Decision {
+    pub fn error(&self) -> f64 {
+        self.best_equity - self.equity
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Luck {
+    pub index: usize,
+    // The player the roll is for
+    pub player: bool,
+    pub position: Backgammon,
+    pub roll: (u8, u8),
+    // Points the roll is worth to the player compared to an average roll
+    pub luck: f64,
+}
+
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Summary {
+    // Decisions with more than one choice
+    pub decisions: usize,
+    pub total_error: f64,
+    pub doubtful: usize,
+    pub errors: usize,
+    pub blunders: usize,
+    pub luck: f64,
+}
+
+impl Summary {
+    pub fn error_rate(&self) -> f64 {
+        if self.decisions == 0 {
+            0.0
+        } else {
+            self.total_error / self.decisions as f64
+        }
+    }
+
+    // Like the performance rating of other programs, lower is better
+    pub fn rating(&self) -> f64 {
+        RATING_SCALE * self.error_rate()
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct GameReview {
+    pub decisions: Vec<Decision>,
+    pub rolls: Vec<Luck>,
+    // Summaries for (white, black)
+    pub summaries: (Summary, Summary),
+}
+
+impl GameReview {
+    pub fn new(record: &GameRecord, evaluator: &Evaluator<Backgammon>) -> Self {
+        let mut decisions = Vec::new();
+        let mut rolls = Vec::new();
+        let mut summaries = (Summary::default(), Summary::default());
+        let mut s = record.start.clone();
+        for (index, m) in record.moves.iter().enumerate() {
+            match (s.current_player(), m) {
+                (None, &Move::Roll(roll)) => {
+                    let player = s.player;
+                    let sign = if player { 1.0 } else { -1.0 };
+                    let luck = sign * roll_luck(evaluator, &s, roll);
+                    summary(&mut summaries, player).luck += luck;
+                    rolls.push(Luck {
+                        index: index,
+                        player: player,
+                        position: s.clone(),
+                        roll: roll,
+                        luck: luck,
+                    });
+                }
+                (Some(player), _) => {
+                    let moves = s.legal_moves();
+                    if moves.len() > 1 {
+                        let equities: Vec<_> = moves.iter().map(|m| move_equity(evaluator, &s, m, player)).collect();
+                        let (best, best_equity) = moves
+                            .iter()
+                            .zip(&equities)
+                            .fold(None, |best: Option<(&Move, f64)>, (m, &e)| match best {
+                                Some((_, b)) if b >= e => best,
+                                _ => Some((m, e)),
+                            })
+                            .unwrap();
+                        let equity = move_equity(evaluator, &s, m, player);
+                        let error = (best_equity - equity).max(0.0);
+                        let quality = quality(error);
+                        {
+                            let summary = summary(&mut summaries, player);
+                            summary.decisions += 1;
+                            summary.total_error += error;
+                            match quality {
+                                Quality::Good => {}
+                                Quality::Doubtful => summary.doubtful += 1,
+                                Quality::Error => summary.errors += 1,
+                                Quality::Blunder => summary.blunders += 1,
+                            }
+                        }
+                        decisions.push(Decision {
+                            index: index,
+                            player: player,
+                            position: s.clone(),
+                            played: m.clone(),
+                            best: best.clone(),
+                            equity: equity,
+                            best_equity: best_equity,
+                            quality: quality,
+                        });
+                    }
+                }
+                _ => {}
+            }
+            s.apply(m.clone());
+        }
+        GameReview {
+            decisions: decisions,
+            rolls: rolls,
+            summaries: summaries,
+        }
+    }
+}
+
+impl fmt::Display for GameReview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for d in &self.decisions {
+            if d.quality == Quality::Good {
+                continue;
+            }
+            writeln!(
+                f,
+                "{:4} {}: {:?}, played {} ({:+.3}) instead of {} ({:+.3})",
+                d.index + 1,
+                player_name(d.player),
+                d.quality,
+                notation::format_move(&d.position, &d.played),
+                d.equity,
+                notation::format_move(&d.position, &d.best),
+                d.best_equity
+            )?;
+        }
+        for &(player, ref summary) in &[(true, &self.summaries.0), (false, &self.summaries.1)] {
+            writeln!(
+                f,
+                "{}: {} decisions, error rate {:.3}, rating {:.1}, {} doubtful, {} errors, {} blunders, luck {:+.3}",
+                player_name(player),
+                summary.decisions,
+                summary.error_rate(),
+                summary.rating(),
+                summary.doubtful,
+                summary.errors,
+                summary.blunders,
+                summary.luck
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Expected points for the player after the move,
+// counting positions the evaluator doesn't know as even
+fn move_equity(evaluator: &Evaluator<Backgammon>, s: &Backgammon, m: &Move, player: bool) -> f64 {
+    let mut new = s.clone();
+    new.apply(m.clone());
+    if new.current_player() == Some(!player) && new.player == player {
+        // The opponent answers a double as well as they can
+        return new.legal_moves()
+            .iter()
+            .map(|response| -move_equity(evaluator, &new, response, !player))
+            .fold(::std::f64::INFINITY, f64::min);
+    }
+    let sign = if player { 1.0 } else { -1.0 };
+    sign * value(evaluator, &new).unwrap_or(0.0)
+}
+
+// How bad an error of so many points is
+pub fn quality(error: f64) -> Quality {
+    if error >= BLUNDER {
+        Quality::Blunder
+    } else if error >= ERROR {
+        Quality::Error
+    } else if error >= DOUBTFUL {
+        Quality::Doubtful
+    } else {
+        Quality::Good
+    }
+}
+
+fn summary(summaries: &mut (Summary, Summary), player: bool) -> &mut Summary {
+    if player {
+        &mut summaries.0
+    } else {
+        &mut summaries.1
+    }
+}
+
+fn player_name(player: bool) -> &'static str {
+    if player { "White" } else { "Black" }
+}
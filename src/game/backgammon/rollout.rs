@@ -0,0 +1,214 @@
+// Rollouts: playing a position out many times with a fixed policy,
+// choosing the play the evaluator likes best for every roll.
+// The cube stays where it is
+use game::{Evaluator, GameState};
+use super::{Backgammon, CubeAction, Move, Phase, Roll};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use std::sync::Arc;
+use std::thread;
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct RolloutSettings {
+    pub trials: usize,
+    // Turns to play before settling for the evaluator's estimate, None to play to the end
+    pub truncation: Option<usize>,
+    pub threads: usize,
+    // Results depend only on the seed, not on the number of threads
+    pub seed: u32,
+    // Rotate the first two rolls through all 36 possibilities rather than rolling them
+    pub quasi_random: bool,
+    // Subtract the luck of every roll as judged by the evaluator,
+    // that is how much better the roll is than an average one
+    pub luck_adjustment: bool,
+}
+
+impl Default for RolloutSettings {
+    fn default() -> Self {
+        RolloutSettings {
+            trials: 1296,
+            truncation: None,
+            threads: 1,
+            seed: 1,
+            quasi_random: true,
+            luck_adjustment: true,
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Estimate {
+    // Expected points for the player whose turn it is, the one to take or drop after a double
+    pub equity: f64,
+    pub standard_error: f64,
+    pub trials: usize,
+}
+
+pub fn rollout(
+    s: &Backgammon,
+    evaluator: Arc<Evaluator<Backgammon>>,
+    settings: &RolloutSettings,
+) -> Result<Estimate, String> {
+    if settings.trials == 0 {
+        Err("A rollout needs at least one trial")?;
+    }
+    let results = trials(s, evaluator, settings);
+    let n = results.len() as f64;
+    let sign = if mover(s) { 1.0 } else { -1.0 };
+    let mean = results.iter().sum::<f64>() / n;
+    let variance = if n > 1.0 {
+        results.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)
+    } else {
+        0.0
+    };
+    Ok(Estimate {
+        equity: sign * mean,
+        standard_error: (variance / n).sqrt(),
+        trials: results.len(),
+    })
+}
+
+// Every legal play after a roll with its rollout, best first.
+// The candidates all see the same dice, so their differences are measured more precisely
+pub fn rollout_moves(
+    s: &Backgammon,
+    evaluator: Arc<Evaluator<Backgammon>>,
+    settings: &RolloutSettings,
+) -> Result<Vec<(Move, Estimate)>, String> {
+    let mut v = s.legal_moves()
+        .into_iter()
+        .map(|m| {
+            let mut new = s.clone();
+            new.apply(m.clone());
+            let mut estimate = rollout(&new, evaluator.clone(), settings)?;
+            // The turn has passed to the opponent
+            if mover(&new) != mover(s) {
+                estimate.equity = -estimate.equity;
+            }
+            Ok((m, estimate))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    v.sort_by(|a, b| b.1.equity.total_cmp(&a.1.equity));
+    Ok(v)
+}
+
+// The player with a decision to make, or to roll
+fn mover(s: &Backgammon) -> bool {
+    s.current_player().unwrap_or(s.player)
+}
+
+// Results of every trial for white, in the order of the trials
+fn trials(s: &Backgammon, evaluator: Arc<Evaluator<Backgammon>>, settings: &RolloutSettings) -> Vec<f64> {
+    let threads = settings.threads.max(1);
+    let handles: Vec<_> = (0..threads)
+        .map(|t| {
+            let s = s.clone();
+            let evaluator = evaluator.clone();
+            let settings = settings.clone();
+            thread::spawn(move || {
+                (0..settings.trials)
+                    .filter(|i| i % threads == t)
+                    .map(|i| (i, trial(&s, &*evaluator, &settings, i)))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut results = vec![0.0; settings.trials];
+    for handle in handles {
+        for (i, r) in handle.join().expect("Rollout thread panicked") {
+            results[i] = r;
+        }
+    }
+    results
+}
+
+fn trial(start: &Backgammon, evaluator: &Evaluator<Backgammon>, settings: &RolloutSettings, index: usize) -> f64 {
+    // Each trial has its own dice, whichever thread plays it
+    let mut rng = XorShiftRng::from_seed([settings.seed, index as u32, 0x9e37_79b9, 0x7f4a_7c15]);
+    let mut s = start.clone();
+    let mut luck = 0.0;
+    let mut turns = 0;
+    let mut rolls = 0;
+    loop {
+        if let Some(scores) = s.scores() {
+            return scores[&Some(true)] - luck;
+        }
+        if settings.truncation.map_or(false, |t| turns >= t) {
+            if let Some(v) = value(evaluator, &s) {
+                return v - luck;
+            }
+        }
+        match s.phase {
+            Phase::Cube => s.apply(Move::Cube(CubeAction::NoDouble)),
+            Phase::Take => s.apply(Move::Cube(CubeAction::Take)),
+            Phase::Roll => {
+                let roll = if settings.quasi_random && rolls < 2 {
+                    let n = if rolls == 0 { index % 36 } else { index / 36 % 36 };
+                    canonical(n as u8 / 6 + 1, n as u8 % 6 + 1)
+                } else {
+                    canonical(rng.gen_range(1, 7), rng.gen_range(1, 7))
+                };
+                if settings.luck_adjustment {
+                    luck += roll_luck(evaluator, &s, roll);
+                }
+                s.apply(Move::Roll(roll));
+                rolls += 1;
+            }
+            Phase::Play => {
+                let m = best_play(evaluator, &s).0;
+                s.apply(m);
+                turns += 1;
+            }
+            Phase::Dropped => unreachable!("Dropped games are finished"),
+        }
+    }
+}
+
+// How much better for white the roll is than the average roll
+pub fn roll_luck(evaluator: &Evaluator<Backgammon>, s: &Backgammon, roll: Roll) -> f64 {
+    let after = |roll: Roll| {
+        let mut new = s.clone();
+        new.apply(Move::Roll(roll));
+        best_play(evaluator, &new).1
+    };
+    let mut average = 0.0;
+    for x in 1..7 {
+        for y in 1..(x + 1) {
+            let chance = if x == y { 1.0 / 36.0 } else { 2.0 / 36.0 };
+            match after((x, y)) {
+                Some(v) => average += chance * v,
+                None => return 0.0,
+            }
+        }
+    }
+    after(roll).map_or(0.0, |v| v - average)
+}
+
+// The play the evaluator rates best for the player, with its value for white
+pub fn best_play(evaluator: &Evaluator<Backgammon>, s: &Backgammon) -> (Move, Option<f64>) {
+    let sign = if s.player { 1.0 } else { -1.0 };
+    let mut best: Option<(Move, Option<f64>)> = None;
+    for m in s.legal_moves() {
+        let mut new = s.clone();
+        new.apply(m.clone());
+        let v = value(evaluator, &new);
+        let better = match best {
+            None => true,
+            Some((_, b)) => v.map_or(false, |v| b.map_or(true, |b| sign * v > sign * b)),
+        };
+        if better {
+            best = Some((m, v));
+        }
+    }
+    best.expect("Playing always has a legal move")
+}
+
+// Expected points for white
+pub fn value(evaluator: &Evaluator<Backgammon>, s: &Backgammon) -> Option<f64> {
+    s.scores()
+        .or_else(|| evaluator.evaluate(s))
+        .map(|scores| scores[&Some(true)])
+}
+
+fn canonical(x: u8, y: u8) -> Roll {
+    (x.max(y), x.min(y))
+}
@@ -0,0 +1,196 @@
+// Dropping stones into columns to get four in a row, on the usual board 7 columns wide and 6 high
+// or on others. The first player's stones are X and the second's O
+use game::{GameState, Notation, Score};
+use play::Render;
+use std::collections::HashMap;
+
+// A bit for each cell, a column after another from the left and each from the bottom.
+// Columns are one bit higher than the board, the top bit always clear,
+// so that no line runs from the top of a column into the next
+type Bits = u64;
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct ConnectFour {
+    width: u8,
+    height: u8,
+    // The first player's stones, then the second's
+    stones: [Bits; 2],
+    player: bool,
+    winner: Option<bool>,
+}
+
+impl GameState for ConnectFour {
+    // The column, from 0 on the left
+    type Move = u8;
+    type Player = bool;
+
+    fn new() -> Self {
+        ConnectFour::with_size(7, 6).unwrap()
+    }
+
+    fn apply(&mut self, column: u8) {
+        let p = self.player;
+        // Adding the bottom cell carries up to the lowest empty one
+        let stone = (self.mask() + self.bottom(column)) & self.column(column);
+        self.stones[p as usize] |= stone;
+        if has_four(self.stones[p as usize], self.height) {
+            self.winner = Some(p);
+        }
+        self.player = !p;
+    }
+
+    // From the middle out, which is the order good moves are usually in
+    fn legal_moves(&self) -> Vec<u8> {
+        let mut moves: Vec<u8> = (0..self.width).filter(|&c| self.mask() & self.top(c) == 0).collect();
+        moves.sort_by_key(|&c| (2 * c as i32 - (self.width as i32 - 1)).abs());
+        moves
+    }
+
+    fn players() -> Vec<bool> {
+        vec![false, true]
+    }
+
+    fn current_player(&self) -> bool {
+        self.player
+    }
+
+    fn scores(&self) -> Option<HashMap<bool, Score>> {
+        if !self.finished() {
+            return None;
+        }
+        let mut m = HashMap::new();
+        for p in ConnectFour::players() {
+            m.insert(p, self.winner.map_or(0.0, |w| if w == p { 1.0 } else { -1.0 }));
+        }
+        Some(m)
+    }
+
+    fn finished(&self) -> bool {
+        self.winner.is_some() || self.moves() == self.width as u32 * self.height as u32
+    }
+}
+
+// Shifting by a direction pairs each stone with the next along it, shifting twice as far
+// pairs those pairs, leaving a stone where four start
+fn has_four(stones: Bits, height: u8) -> bool {
+    let h = height as u32;
+    [1, h + 1, h, h + 2].iter().any(|&d| {
+        let pairs = stones & stones.checked_shr(d).unwrap_or(0);
+        pairs & pairs.checked_shr(2 * d).unwrap_or(0) != 0
+    })
+}
+
+impl ConnectFour {
+    // Boards need a bit for each cell and one more for each column, 64 at most
+    pub fn with_size(width: u8, height: u8) -> Result<Self, String> {
+        if width == 0 || height == 0 || width as u32 * (height as u32 + 1) > 64 {
+            Err(format!("A board {} wide and {} high doesn't fit in 64 bits", width, height))?;
+        }
+        Ok(ConnectFour {
+            width: width,
+            height: height,
+            stones: [0; 2],
+            player: false,
+            winner: None,
+        })
+    }
+
+    // Columns numbered from 1 with a digit each, such as "4453" for two stones
+    // in the middle of the usual board, one in the column to the right and one to the left
+    pub fn from_moves(width: u8, height: u8, moves: &str) -> Result<Self, String> {
+        let mut s = ConnectFour::with_size(width, height)?;
+        for c in moves.chars().filter(|c| !c.is_whitespace()) {
+            if s.finished() {
+                Err(format!("The game is over before the moves {} end", moves))?;
+            }
+            let m = s.parse_move(&c.to_string())?;
+            s.apply(m);
+        }
+        Ok(s)
+    }
+
+    pub fn size(&self) -> (u8, u8) {
+        (self.width, self.height)
+    }
+
+    pub fn winner(&self) -> Option<bool> {
+        self.winner
+    }
+
+    pub fn moves(&self) -> u32 {
+        self.mask().count_ones()
+    }
+
+    // The player whose stone is in the cell, rows counted from the bottom
+    pub fn at(&self, column: u8, row: u8) -> Option<bool> {
+        let cell = self.bottom(column) << row;
+        if self.stones[0] & cell != 0 {
+            Some(false)
+        } else if self.stones[1] & cell != 0 {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    fn mask(&self) -> Bits {
+        self.stones[0] | self.stones[1]
+    }
+
+    fn bottom(&self, column: u8) -> Bits {
+        1 << (column as u32 * (self.height as u32 + 1))
+    }
+
+    fn top(&self, column: u8) -> Bits {
+        self.bottom(column) << (self.height - 1)
+    }
+
+    fn column(&self, column: u8) -> Bits {
+        ((1 << self.height) - 1) * self.bottom(column)
+    }
+}
+
+// Columns numbered from 1 on the left
+impl Notation for ConnectFour {
+    fn parse_move(&self, text: &str) -> Result<u8, String> {
+        let text = text.trim();
+        let c: u8 = text.parse().map_err(|_| format!("{} isn't a column number", text))?;
+        if c == 0 || c > self.width {
+            Err(format!("There is no column {}", c))?;
+        }
+        if !self.legal_moves().contains(&(c - 1)) {
+            Err(format!("Column {} is full", c))?;
+        }
+        Ok(c - 1)
+    }
+
+    fn format_move(&self, &c: &u8) -> String {
+        (c + 1).to_string()
+    }
+}
+
+impl Render for ConnectFour {
+    fn render(&self) -> String {
+        let mut lines = Vec::new();
+        for row in (0..self.height).rev() {
+            let cells: String = (0..self.width)
+                .map(|c| match self.at(c, row) {
+                    Some(false) => " X",
+                    Some(true) => " O",
+                    None => " .",
+                })
+                .collect();
+            lines.push(cells);
+        }
+        lines.push((1..(self.width + 1)).map(|c| format!("{:>2}", c)).collect());
+        lines.join("\n")
+    }
+
+    fn player_name(p: &bool) -> String {
+        if *p { "O" } else { "X" }.to_string()
+    }
+
+    fn move_help() -> String {
+        "Write the number of the column to drop a stone in".to_string()
+    }
+}
@@ -0,0 +1,231 @@
+// Getting k stones in a row on a board m cells wide and n high,
+// such as tic-tac-toe, the 3,3,3-game, and gomoku, the 15,15,5-game.
+// The first player's stones are X and the second's O
+use game::{GameState, Notation, Score};
+use play::Render;
+use std::collections::HashMap;
+
+const MAX_SIZE: u8 = 15;
+const WORDS: usize = 4;
+
+// A bit for each cell, a row after another. Rows are m + 1 bits long, the last always clear,
+// so that no line runs from the end of a row into the next
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Default)]
+struct Bits([u64; WORDS]);
+
+impl Bits {
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    // Moves every bit d places down, d being under 64
+    fn shift(&self, d: usize) -> Bits {
+        let mut bits = [0; WORDS];
+        for i in 0..WORDS {
+            bits[i] = self.0[i] >> d;
+            if d > 0 && i + 1 < WORDS {
+                bits[i] |= self.0[i + 1] << (64 - d);
+            }
+        }
+        Bits(bits)
+    }
+
+    fn and(&self, other: &Bits) -> Bits {
+        let mut bits = self.0;
+        for i in 0..WORDS {
+            bits[i] &= other.0[i];
+        }
+        Bits(bits)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+}
+
+// Column and row, from the bottom left
+pub type Cell = (u8, u8);
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct MnkGame {
+    m: u8,
+    n: u8,
+    k: u8,
+    // The first player's stones, then the second's
+    stones: [Bits; 2],
+    player: bool,
+    winner: Option<bool>,
+    empty: u16,
+}
+
+impl GameState for MnkGame {
+    type Move = Cell;
+    type Player = bool;
+
+    // Tic-tac-toe
+    fn new() -> Self {
+        MnkGame::with_size(3, 3, 3).unwrap()
+    }
+
+    fn apply(&mut self, (x, y): Cell) {
+        let p = self.player;
+        let i = self.index(x, y);
+        let stones = &mut self.stones[p as usize];
+        stones.set(i);
+        if has_line(stones, self.m, self.k) {
+            self.winner = Some(p);
+        }
+        self.empty -= 1;
+        self.player = !p;
+    }
+
+    fn legal_moves(&self) -> Vec<Cell> {
+        let mut moves = Vec::with_capacity(self.empty as usize);
+        for y in 0..self.n {
+            for x in 0..self.m {
+                if self.at(x, y).is_none() {
+                    moves.push((x, y));
+                }
+            }
+        }
+        moves
+    }
+
+    fn players() -> Vec<bool> {
+        vec![false, true]
+    }
+
+    fn current_player(&self) -> bool {
+        self.player
+    }
+
+    fn scores(&self) -> Option<HashMap<bool, Score>> {
+        if !self.finished() {
+            return None;
+        }
+        let mut m = HashMap::new();
+        for p in MnkGame::players() {
+            m.insert(p, self.winner.map_or(0.0, |w| if w == p { 1.0 } else { -1.0 }));
+        }
+        Some(m)
+    }
+
+    fn finished(&self) -> bool {
+        self.winner.is_some() || self.empty == 0
+    }
+}
+
+// Lines of k stones, found by shifting the stones along each direction k - 1 times
+fn has_line(stones: &Bits, m: u8, k: u8) -> bool {
+    let row = m as usize + 1;
+    [1, row, row + 1, row - 1].iter().any(|&d| {
+        let mut line = *stones;
+        for _ in 1..k {
+            line = line.and(&line.shift(d));
+        }
+        !line.is_empty()
+    })
+}
+
+impl MnkGame {
+    // Boards are at most 15 by 15
+    pub fn with_size(m: u8, n: u8, k: u8) -> Result<Self, String> {
+        if m == 0 || n == 0 || m > MAX_SIZE || n > MAX_SIZE {
+            Err(format!("Boards are 1 to {} cells wide and high", MAX_SIZE))?;
+        }
+        if k == 0 {
+            Err("Lines need at least one stone")?;
+        }
+        Ok(MnkGame {
+            m: m,
+            n: n,
+            k: k,
+            stones: [Bits::default(); 2],
+            player: false,
+            winner: None,
+            empty: m as u16 * n as u16,
+        })
+    }
+
+    pub fn size(&self) -> (u8, u8, u8) {
+        (self.m, self.n, self.k)
+    }
+
+    // The player whose stone is in the cell
+    pub fn at(&self, x: u8, y: u8) -> Option<bool> {
+        let i = self.index(x, y);
+        if self.stones[0].get(i) {
+            Some(false)
+        } else if self.stones[1].get(i) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    pub fn winner(&self) -> Option<bool> {
+        self.winner
+    }
+
+    fn index(&self, x: u8, y: u8) -> usize {
+        y as usize * (self.m as usize + 1) + x as usize
+    }
+}
+
+// Cells such as "b3", a column letter and a row number from 1
+impl Notation for MnkGame {
+    fn parse_move(&self, text: &str) -> Result<Cell, String> {
+        let text = text.trim().to_lowercase();
+        let mut chars = text.chars();
+        let x = match chars.next() {
+            Some(c) if c >= 'a' && c <= 'z' => c as u8 - b'a',
+            _ => Err(format!("{} doesn't start with a column letter", text))?,
+        };
+        let y: u8 = chars
+            .as_str()
+            .parse()
+            .map_err(|_| format!("{} doesn't end with a row number", text))?;
+        if x >= self.m || y == 0 || y > self.n {
+            Err(format!("{} is off the board", text))?;
+        }
+        let cell = (x, y - 1);
+        if !self.legal_moves().contains(&cell) {
+            Err(format!("{} isn't a legal move", text))?;
+        }
+        Ok(cell)
+    }
+
+    fn format_move(&self, &(x, y): &Cell) -> String {
+        format!("{}{}", (b'a' + x) as char, y + 1)
+    }
+}
+
+impl Render for MnkGame {
+    fn render(&self) -> String {
+        let letters: String = (0..self.m).map(|x| format!(" {}", (b'a' + x) as char)).collect();
+        let mut lines = vec![format!("  {}", letters)];
+        for y in (0..self.n).rev() {
+            let cells: String = (0..self.m)
+                .map(|x| match self.at(x, y) {
+                    Some(false) => " X",
+                    Some(true) => " O",
+                    None => " .",
+                })
+                .collect();
+            lines.push(format!("{:>2}{}", y + 1, cells));
+        }
+        lines.join("\n")
+    }
+
+    fn player_name(p: &bool) -> String {
+        if *p { "O" } else { "X" }.to_string()
+    }
+
+    fn move_help() -> String {
+        "Write the cell to play in, such as b2".to_string()
+    }
+}
@@ -1,5 +1,8 @@
 pub mod nim;
 pub mod backgammon;
+pub mod mnk;
+pub mod connect_four;
+pub mod othello;
 
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -35,3 +38,27 @@ pub trait GameState: Eq + Hash + Clone + Sync + Send {
         }
     }
 }
+
+// Knowledge of how games end, which search can use instead of playing on
+pub trait Evaluator<G: GameState>: Sync + Send {
+    // Expected scores of every player, None where the evaluator can't tell
+    fn evaluate(&self, s: &G) -> Option<ScoreBoard<G>>;
+}
+
+
+// Moves written out for people to read and type
+pub trait Notation: GameState {
+    // Only legal moves are read
+    fn parse_move(&self, text: &str) -> Result<Self::Move, String>;
+    fn format_move(&self, m: &Self::Move) -> String;
+}
+
+// Positions written out for other programs to read
+pub trait Codec: Notation {
+    fn parse_position(text: &str) -> Result<Self, String>;
+    fn format_position(&self) -> String;
+    // Sets the dice for the player about to roll them, in games that have dice
+    fn set_dice(&mut self, _text: &str) -> Result<(), String> {
+        Err("This game has no dice".to_string())
+    }
+}
@@ -1,4 +1,5 @@
-use game::{GameState, Score};
+use game::{Codec, GameState, Notation, Score};
+use play::Render;
 use std::collections::HashMap;
 use std::cmp::min;
 
@@ -46,3 +47,66 @@ impl GameState for Nim {
         self.0 >= 100
     }
 }
+
+// Moves are the number added to the total
+impl Notation for Nim {
+    fn parse_move(&self, text: &str) -> Result<Self::Move, String> {
+        let m = text.trim().parse().map_err(|_| format!("{} isn't a number", text.trim()))?;
+        if !self.legal_moves().contains(&m) {
+            Err(format!("{} isn't a legal move", m))?;
+        }
+        Ok(m)
+    }
+
+    fn format_move(&self, m: &Self::Move) -> String {
+        m.to_string()
+    }
+}
+
+// The total and whose turn it is, 0 for the first player and 1 for the second
+impl Codec for Nim {
+    fn parse_position(text: &str) -> Result<Self, String> {
+        let words: Vec<_> = text.split_whitespace().collect();
+        if words.len() != 2 {
+            Err(format!("Expected a total and a player, found {}", text))?;
+        }
+        let total = words[0].parse().map_err(|_| format!("{} isn't a number", words[0]))?;
+        if total > 100 {
+            Err("The total can't be over 100")?;
+        }
+        let player = match words[1] {
+            "0" => false,
+            "1" => true,
+            p => Err(format!("{} isn't a player", p))?,
+        };
+        Ok(Nim::with_total(total, player))
+    }
+
+    fn format_position(&self) -> String {
+        format!("{} {}", self.0, if self.1 { 1 } else { 0 })
+    }
+}
+
+impl Render for Nim {
+    fn render(&self) -> String {
+        format!("The total is {}", self.0)
+    }
+
+    fn player_name(p: &bool) -> String {
+        if *p { "Second player" } else { "First player" }.to_string()
+    }
+
+    fn move_help() -> String {
+        "Write how much to add to the total".to_string()
+    }
+}
+
+impl Nim {
+    pub fn with_total(total: u32, player: bool) -> Self {
+        Nim(total, player)
+    }
+
+    pub fn total(&self) -> u32 {
+        self.0
+    }
+}
@@ -0,0 +1,240 @@
+// Othello, or Reversi: discs placed to flank the opponent's are flipped over,
+// players without such a move pass, and the game ends when neither can move.
+// Scores are the difference in discs, Black moving first with X and White with O
+use game::{GameState, Notation, Score};
+use play::Render;
+use std::collections::HashMap;
+
+// A bit for each square, a row after another from a1 at the top left to h8
+type Bits = u64;
+
+const FILE_A: Bits = 0x0101010101010101;
+const FILE_H: Bits = 0x8080808080808080;
+
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum Move {
+    // The square, from 0 for a1 to 63 for h8
+    Place(u8),
+    Pass,
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+enum Direction {
+    East,
+    West,
+    South,
+    North,
+    SouthEast,
+    SouthWest,
+    NorthEast,
+    NorthWest,
+}
+use self::Direction::*;
+
+const DIRECTIONS: [Direction; 8] = [East, West, South, North, SouthEast, SouthWest, NorthEast, NorthWest];
+
+// Moves every disc a square along the direction, dropping those going off the board
+fn shift(b: Bits, d: Direction) -> Bits {
+    match d {
+        East => (b << 1) & !FILE_A,
+        West => (b >> 1) & !FILE_H,
+        South => b << 8,
+        North => b >> 8,
+        SouthEast => (b << 9) & !FILE_A,
+        SouthWest => (b << 7) & !FILE_H,
+        NorthEast => (b >> 7) & !FILE_A,
+        NorthWest => (b >> 9) & !FILE_H,
+    }
+}
+
+// Squares where the player can flank some of the opponent's discs
+fn moves(own: Bits, other: Bits) -> Bits {
+    let empty = !(own | other);
+    let mut moves = 0;
+    for &d in &DIRECTIONS {
+        // Runs of the opponent's discs next to the player's are at most 6 long
+        let mut run = shift(own, d) & other;
+        for _ in 0..5 {
+            run |= shift(run, d) & other;
+        }
+        moves |= shift(run, d) & empty;
+    }
+    moves
+}
+
+// The opponent's discs flanked by a disc placed on the square
+fn flips(own: Bits, other: Bits, square: u8) -> Bits {
+    let placed = 1 << square;
+    let mut flipped = 0;
+    for &d in &DIRECTIONS {
+        let mut run = 0;
+        let mut next = shift(placed, d);
+        while next & other != 0 {
+            run |= next;
+            next = shift(next, d);
+        }
+        if next & own != 0 {
+            flipped |= run;
+        }
+    }
+    flipped
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Othello {
+    // Black's discs, then White's
+    discs: [Bits; 2],
+    player: bool,
+}
+
+impl GameState for Othello {
+    type Move = Move;
+    // False for Black
+    type Player = bool;
+
+    fn new() -> Self {
+        // d5 and e4 for Black, d4 and e5 for White
+        Othello::with_discs(1 << 35 | 1 << 28, 1 << 27 | 1 << 36, false)
+    }
+
+    fn apply(&mut self, m: Move) {
+        if let Move::Place(square) = m {
+            let (own, other) = self.sides();
+            let flipped = flips(own, other, square);
+            let p = self.player as usize;
+            self.discs[p] |= flipped | 1 << square;
+            self.discs[1 - p] &= !flipped;
+        }
+        self.player = !self.player;
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        let (own, other) = self.sides();
+        let mut bits = moves(own, other);
+        if bits == 0 {
+            return vec![Move::Pass];
+        }
+        let mut ms = Vec::with_capacity(bits.count_ones() as usize);
+        while bits != 0 {
+            ms.push(Move::Place(bits.trailing_zeros() as u8));
+            bits &= bits - 1;
+        }
+        ms
+    }
+
+    fn players() -> Vec<bool> {
+        vec![false, true]
+    }
+
+    fn current_player(&self) -> bool {
+        self.player
+    }
+
+    fn scores(&self) -> Option<HashMap<bool, Score>> {
+        if !self.finished() {
+            return None;
+        }
+        let (black, white) = self.counts();
+        let difference = black as Score - white as Score;
+        let mut m = HashMap::new();
+        m.insert(false, difference);
+        m.insert(true, -difference);
+        Some(m)
+    }
+
+    fn finished(&self) -> bool {
+        let (own, other) = self.sides();
+        moves(own, other) == 0 && moves(other, own) == 0
+    }
+}
+
+impl Othello {
+    // Bits as in `at`, squares with discs of both colours being Black's
+    pub fn with_discs(black: u64, white: u64, player: bool) -> Self {
+        Othello {
+            discs: [black, white & !black],
+            player: player,
+        }
+    }
+
+    // The player whose disc is on the square, counting columns and rows from 0 at a1
+    pub fn at(&self, column: u8, row: u8) -> Option<bool> {
+        let square: Bits = 1 << (row * 8 + column);
+        if self.discs[0] & square != 0 {
+            Some(false)
+        } else if self.discs[1] & square != 0 {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    // Black's discs and White's
+    pub fn counts(&self) -> (u32, u32) {
+        (self.discs[0].count_ones(), self.discs[1].count_ones())
+    }
+
+    // The player's discs and the opponent's
+    fn sides(&self) -> (Bits, Bits) {
+        let p = self.player as usize;
+        (self.discs[p], self.discs[1 - p])
+    }
+}
+
+// Squares such as "d3", a column letter and a row number counted from the top, or "pass"
+impl Notation for Othello {
+    fn parse_move(&self, text: &str) -> Result<Move, String> {
+        let text = text.trim().to_lowercase();
+        let m = if text == "pass" {
+            Move::Pass
+        } else {
+            let bytes = text.as_bytes();
+            if bytes.len() != 2 || bytes[0] < b'a' || bytes[0] > b'h' || bytes[1] < b'1' || bytes[1] > b'8' {
+                Err(format!("{} isn't a square such as d3", text))?;
+            }
+            Move::Place((bytes[1] - b'1') * 8 + bytes[0] - b'a')
+        };
+        if !self.legal_moves().contains(&m) {
+            Err(format!("{} isn't a legal move", text))?;
+        }
+        Ok(m)
+    }
+
+    fn format_move(&self, m: &Move) -> String {
+        match *m {
+            Move::Place(square) => format!("{}{}", (b'a' + square % 8) as char, square / 8 + 1),
+            Move::Pass => "pass".to_string(),
+        }
+    }
+}
+
+impl Render for Othello {
+    fn render(&self) -> String {
+        let mut lines = vec!["   a b c d e f g h".to_string()];
+        for row in 0..8 {
+            let squares: String = (0..8)
+                .map(|column| match self.at(column, row) {
+                    Some(false) => " X",
+                    Some(true) => " O",
+                    None => " .",
+                })
+                .collect();
+            lines.push(format!("{:>2}{}", row + 1, squares));
+        }
+        let (black, white) = self.counts();
+        lines.push(format!("Black (X) has {} discs, White (O) {}", black, white));
+        lines.join("\n")
+    }
+
+    fn player_name(p: &bool) -> String {
+        if *p { "White" } else { "Black" }.to_string()
+    }
+
+    fn move_help() -> String {
+        "Write the square to place a disc on, such as d3, or pass when there is none".to_string()
+    }
+
+    fn winnings(score: Score) -> Option<String> {
+        Some(format!("by {} discs", score))
+    }
+}
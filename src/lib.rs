@@ -1,5 +1,12 @@
-#![feature(type_ascription)]
+extern crate rand;
 
 pub mod game;
 
 pub mod mcts_hashtable;
+pub mod arena;
+pub mod engine;
+pub mod play;
+pub mod options;
+pub mod protocol;
+pub mod saved;
+pub mod solver;
@@ -1,24 +1,74 @@
-#![feature(type_ascription)]
-
 extern crate game_trees;
-extern crate itertools;
 
+use game_trees::arena::{Arena, Chance, Format, Sprt, Uniform};
 use game_trees::game::GameState;
 use game_trees::game::backgammon;
-use backgammon::Backgammon;
-use backgammon::{Point, board};
-use backgammon::Location::*;
-use game_trees::mcts_hashtable::MctsTable;
+use game_trees::game::connect_four::ConnectFour;
+use game_trees::game::mnk::MnkGame;
+use game_trees::game::nim::Nim;
+use game_trees::game::othello::Othello;
+use backgammon::{Backgammon, Move};
+use backgammon::dice::{Dice, FairDice, RandomDice, ReplayDice, Roller};
+use backgammon::notation;
+use backgammon::records::{GameRecord, MatchRecord};
+use game_trees::engine::{Engine, EngineSettings};
+use game_trees::options::{engine_config, value, Options};
+use game_trees::play;
+use game_trees::play::{Controller, EnginePlayer, Human, Kind, Render, Seat, Session};
+use game_trees::protocol::Protocol;
+use game_trees::saved::SavedGame;
 
+use std::env;
 use std::error::Error;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::sync::Arc;
 use std::io;
 use std::io::Write;
-use std::time::Duration;
-use itertools::Itertools;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type BoxResult<T> = Result<T, Box<dyn Error>>;
+
+const USAGE: &'static str = "Usage: game-trees <game> [options]
+       game-trees resume <file>      continue a game saved with the save command
+       game-trees tournament <game> [tournament options]
+       game-trees protocol <game>    a line based protocol for other programs, see src/protocol.rs,
+                                     for nim and backgammon
+
+Games:
+    nim                  add 1 to 10 to a total, whoever reaches 100 wins
+    mnk                  get k stones in a row on an m by n board, tic-tac-toe unless given a size
+    connect-four         drop stones into columns to get four in a row
+    othello              flip the opponent's discs by flanking them, most discs at the end wins
+    backgammon
+
+Options:
+    --mode <mode>        human-engine (the default), engine-engine or human-human
+    --first <side>       human (the default) or engine, who moves first against the engine
+    --playouts <n>       playouts the engine waits for before moving, 32 per legal move by default
+    --time <seconds>     longest the engine thinks about a move, 7 by default
+    --threads <n>        threads searching for the engine, 1 by default
+    --exploration <c>    exploration constant of the search, 1.414 by default
 
-type BoxResult<T> = Result<T, Box<Error>>;
+Mnk options:
+    --size <m,n,k>       board width and height and the length of lines, such as 15,15,5 for gomoku
+
+Connect four options:
+    --size <w,h>         board width and height, 7,6 by default
+
+Backgammon options:
+    --cube               play with the doubling cube
+    --dice <dice>        random (the default), manual or a file of rolls to replay
+    --seed <n>           seed of random dice
+
+Tournament options:
+    --engine <config>    an engine such as name=wide,playouts=800,exploration=2,max-its=400,
+                         give at least two
+    --format <format>    round-robin (the default) or gauntlet, the first engine against the others
+    --games <n>          games per pairing, 100 by default
+    --threads <n>        games played at once, 1 by default
+    --seed <n>           seed of the games
+    --sprt <elo0,elo1>   stop a pairing once a sequential test tells which Elo difference is right
+    --cube               play backgammon with the doubling cube
+    --size <size>        the size of the board in m,n,k-games and connect four";
 
 fn main() {
     if let Err(e) = run() {
@@ -27,247 +77,339 @@ fn main() {
 }
 
 fn run() -> BoxResult<()> {
-    let mut s = Backgammon::new();
-    let gt = Arc::new(Mutex::new(MctsTable::<Backgammon>::with_state(s.clone())));
-    println!("Let's play Backgammon. Do you want to go first? If so write \"yes\"");
-    let mut buf = String::new();
-    buf.clear();
-    io::stdin().read_line(&mut buf)?;
-    let mut human_turn = buf.trim() == "yes";
-    let s_ref = Arc::new(Mutex::new(s.clone()));
-    // TODO change mcts_hashtable to use a concurrent hashtable,
-    // allowing for more workers
-    for _ in 0..1 {
-        let gt = gt.clone();
-        let s_ref = s_ref.clone();
-        let mut s = s.clone();
-        let mut wait = false;
-        thread::spawn(move || loop {
-            {
-                let s_locked = s_ref.lock().unwrap();
-                if *s_locked != s {
-                    s = s_locked.clone();
-                    wait = false;
-                }
+    let all: Vec<String> = env::args().skip(1).collect();
+    let mut args = all.iter().cloned();
+    match args.next().as_ref().map(|game| game.as_str()) {
+        Some("nim") => play_game::<Nim, _>(Plain, &all, None),
+        Some("mnk") => play_game(MnkFrontend::default(), &all, None),
+        Some("connect-four") => play_game(ConnectFourFrontend::default(), &all, None),
+        Some("othello") => play_game::<Othello, _>(Plain, &all, None),
+        Some("backgammon") => play_game(BackgammonFrontend::default(), &all, None),
+        Some("resume") => {
+            let saved = SavedGame::load(&value(&mut args, "resume")?)?;
+            match saved.args[0].as_str() {
+                "nim" => play_game::<Nim, _>(Plain, &saved.args.clone(), Some(saved)),
+                "mnk" => play_game(MnkFrontend::default(), &saved.args.clone(), Some(saved)),
+                "connect-four" => play_game(ConnectFourFrontend::default(), &saved.args.clone(), Some(saved)),
+                "othello" => play_game::<Othello, _>(Plain, &saved.args.clone(), Some(saved)),
+                "backgammon" => play_game(BackgammonFrontend::default(), &saved.args.clone(), Some(saved)),
+                game => Err(format!("Unknown game {}", game))?,
             }
-            if !wait {
-                let mut gt = gt.lock().unwrap();
-                for _ in 0..32 {
-                    gt.playout(&s, 400);
-                }
-            } else {
-                thread::sleep(Duration::from_millis(10));
+        }
+        Some("tournament") => match args.next().as_ref().map(|game| game.as_str()) {
+            Some("nim") => tournament::<Nim, _>(Plain, Arc::new(Uniform), &mut args),
+            Some("mnk") => tournament(MnkFrontend::default(), Arc::new(Uniform), &mut args),
+            Some("connect-four") => tournament(ConnectFourFrontend::default(), Arc::new(Uniform), &mut args),
+            Some("othello") => tournament::<Othello, _>(Plain, Arc::new(Uniform), &mut args),
+            Some("backgammon") => tournament(BackgammonFrontend::default(), Arc::new(FairDice), &mut args),
+            Some(game) => Err(format!("Unknown game {}\n\n{}", game, USAGE))?,
+            None => Err(format!("Which game?\n\n{}", USAGE))?,
+        },
+        Some("protocol") => {
+            let stdin = io::stdin();
+            match args.next().as_ref().map(|game| game.as_str()) {
+                Some("nim") => Protocol::<Nim, _>::new(io::stdout()).run(stdin.lock())?,
+                Some("backgammon") => Protocol::<Backgammon, _>::new(io::stdout()).run(stdin.lock())?,
+                Some(game) => Err(format!("Unknown game {}\n\n{}", game, USAGE))?,
+                None => Err(format!("Which game?\n\n{}", USAGE))?,
             }
-            // This constant depends on game state size
-            // and was chosen to remain at a reasonable level of memory use
-            wait = gt.lock().unwrap().0.len() > 2usize.pow(20);
-            // TODO find a better way to ensures main thread can get the lock
-            // within a reasonable timeframe
-            thread::sleep(Duration::from_millis(1));
-        });
+            Ok(())
+        }
+        Some("help") | Some("--help") | Some("-h") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(game) => Err(format!("Unknown game {}\n\n{}", game, USAGE))?,
     }
-    loop {
-        let old_state = s.clone();
-        let d = dice_turn(&mut buf);
-        apply(d, &mut s, &s_ref);
-        gc(&gt, &s, old_state);
-        let old_state = s.clone();
-        if human_turn {
-            let m = move_turn(&mut buf, &s);
-            apply(m, &mut s, &s_ref);
-            if s.finished() {
-                println!("Looks like you won. Congratulations!");
-                break;
-            }
-        } else {
-            let m = computer_turn(&*gt, &mut s)?;
-            print!("My move is: ");
-            for (l, n) in m.clone().unwrap() {
-                print!("{},{} ", l, n);
-            }
-            println!();
-            apply(m, &mut s, &s_ref);
-            if s.finished() {
-                println!("Looks like I won. Too bad!");
-                break;
-            }
+}
+
+// What the binary needs to know about a game besides its notation and rendering
+trait Frontend<G: Render> {
+    // Reads any values of an option from `args`, false if the option is unknown
+    fn option(&mut self, name: &str, args: &mut dyn Iterator<Item = String>) -> BoxResult<bool> {
+        let _ = (name, args);
+        Ok(false)
+    }
+
+    fn start(&mut self) -> BoxResult<G> {
+        Ok(G::new())
+    }
+
+    // The players making choices, the first player first
+    fn sides(&self) -> Vec<G::Player> {
+        G::players()
+    }
+
+    // Whoever makes the moves of players without a side, such as the dice
+    fn chance(&mut self) -> Option<Seat<'static, G>> {
+        None
+    }
+
+    fn finish(&mut self, start: &G, moves: &[G::Move], kinds: &[(G::Player, Kind)]) -> BoxResult<()> {
+        let _ = (start, moves, kinds);
+        Ok(())
+    }
+}
+
+// Games with nothing more to them than their notation and rendering
+struct Plain;
+
+impl<G: Render> Frontend<G> for Plain {}
+
+// The game is named by the first of the arguments, the others are options.
+// A saved game is resumed with the arguments it was started with
+fn play_game<G, F>(mut frontend: F, args: &[String], saved: Option<SavedGame>) -> BoxResult<()>
+where
+    G: Render + 'static,
+    F: Frontend<G>,
+{
+    let options = Options::parse(&mut args[1..].iter().cloned(), |name, args| {
+        frontend.option(name, args).map_err(|e| e.to_string())
+    }).map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+    let kinds = options.kinds::<G>(frontend.sides());
+    let start = frontend.start()?;
+    let mut session = match saved {
+        Some(ref saved) => Session::resume(saved, start)?,
+        None => Session::new(start, args.to_vec()),
+    };
+    let position = session.position();
+    let engine = if kinds.iter().any(|&(_, k)| k == Kind::Engine) {
+        let mut settings = EngineSettings::default();
+        settings.threads = options.threads;
+        settings.exploration = options.exploration;
+        let engine = Engine::new(position.clone(), settings);
+        if let Some(ref saved) = saved {
+            saved.restore(&mut engine.table(), &position)?;
         }
-        human_turn = !human_turn;
-        print_expectations(&s, &*gt);
-        print_state(&s);
-        gc(&gt, &s, old_state);
+        engine.ponder(position);
+        Some(engine)
+    } else {
+        None
+    };
+    let mut seats: Vec<Seat<G>> = Vec::new();
+    for &(ref p, kind) in &kinds {
+        let controller: Box<dyn Controller<G>> = match (kind, engine.as_ref()) {
+            (Kind::Engine, Some(engine)) => {
+                let mut player = EnginePlayer::new(engine);
+                player.playouts = options.playouts;
+                player.time = options.time;
+                Box::new(player)
+            }
+            _ => Box::new(Human),
+        };
+        seats.push((p.clone(), controller));
+    }
+    seats.extend(frontend.chance());
+    play::play(&mut session, &mut seats, engine.as_ref())?;
+    if session.position().finished() {
+        frontend.finish(&session.start, &session.moves, &kinds)?;
     }
     Ok(())
 }
 
-// TODO merge this into mcts_hashtable
-fn gc(gt: &Arc<Mutex<MctsTable<Backgammon>>>, new: &Backgammon, old: Backgammon) {
-    {
-        let gt = gt.clone();
-        let new = new.clone();
-        thread::spawn(move || {
-            let mut gt = gt.lock().unwrap();
-            let old_meta = gt.0[&old].clone();
-            for (_, (s, _)) in old_meta.moves {
-                if s != new {
-                    gt.garbage_collect(&s);
+fn tournament<G, F>(mut frontend: F, chance: Arc<dyn Chance<G>>, args: &mut dyn Iterator<Item = String>) -> BoxResult<()>
+where
+    G: Render + 'static,
+    F: Frontend<G>,
+{
+    let sides = frontend.sides();
+    let mut arena = Arena::new((sides[0].clone(), sides[1].clone()), chance, Vec::new());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine" => {
+                let n = arena.engines.len() + 1;
+                arena.engines.push(engine_config(&value(args, &arg)?, n)?);
+            }
+            "--format" => {
+                arena.settings.format = match value(args, &arg)?.as_str() {
+                    "round-robin" => Format::RoundRobin,
+                    "gauntlet" => Format::Gauntlet,
+                    format => Err(format!("Unknown format {}", format))?,
                 }
             }
-        });
+            "--games" => arena.settings.games = value(args, &arg)?.parse()?,
+            "--threads" => arena.settings.threads = value(args, &arg)?.parse()?,
+            "--seed" => arena.settings.seed = value(args, &arg)?.parse()?,
+            "--sprt" => {
+                let elos = value(args, &arg)?
+                    .split(',')
+                    .map(|elo| elo.parse())
+                    .collect::<Result<Vec<f64>, _>>()?;
+                if elos.len() != 2 {
+                    Err("The test needs two Elo differences, such as 0,10")?;
+                }
+                arena.settings.sprt = Some(Sprt::new(elos[0], elos[1]));
+            }
+            _ => {
+                if !frontend.option(&arg, args)? {
+                    Err(format!("Unknown option {}\n\n{}", arg, USAGE))?;
+                }
+            }
+        }
+    }
+    if arena.engines.len() < 2 {
+        Err("A tournament needs at least two engines")?;
     }
+    arena.start = frontend.start()?;
+    let report = arena.run(|report| {
+        let games: usize = report.pairings.iter().map(|p| p.games).sum();
+        print!("\r{} games played", games);
+        io::stdout().flush().ok();
+    });
+    println!();
+    print!("{}", report);
+    Ok(())
 }
 
-fn apply(m: <Backgammon as GameState>::Move, s: &mut Backgammon, s_ref: &Arc<Mutex<Backgammon>>) {
-    s.apply(m);
-    {
-        *s_ref.lock().unwrap() = s.clone();
+// Numbers such as "15,15,5"
+fn numbers(text: &str, n: usize) -> BoxResult<Vec<u8>> {
+    let numbers = text.split(',').map(|n| n.parse()).collect::<Result<Vec<u8>, _>>()?;
+    if numbers.len() != n {
+        Err(format!("Expected {} numbers separated by commas, found {}", n, text))?;
     }
+    Ok(numbers)
 }
 
-fn dice_turn(buf: &mut String) -> <Backgammon as GameState>::Move {
-    println!("What do the dice show?");
-    println!("Write the lowest number, then a space, then the highest.");
-    loop {
-        match dice_turn_(buf) {
-            Ok(pair) => return Err(pair),
-            Err(e) => println!("{}", e),
+#[derive(Default)]
+struct MnkFrontend {
+    size: Option<(u8, u8, u8)>,
+}
+
+impl Frontend<MnkGame> for MnkFrontend {
+    fn option(&mut self, name: &str, args: &mut dyn Iterator<Item = String>) -> BoxResult<bool> {
+        if name != "--size" {
+            return Ok(false);
         }
+        let size = numbers(&value(args, name)?, 3)?;
+        self.size = Some((size[0], size[1], size[2]));
+        Ok(true)
     }
-}
 
-// Ugly parsing
-fn dice_turn_(mut buf: &mut String) -> BoxResult<backgammon::Roll> {
-    buf.clear();
-    io::stdin().read_line(&mut buf)?;
-    buf.pop().ok_or("Write something!")?;
-    let mut singleton: String = "".into();
-    singleton.push(buf.pop().ok_or("C'mon, write at least one digit!")?);
-    let x = singleton.parse()?;
-    buf.pop().ok_or("You need to write both numbers")?;
-    let mut singleton: String = "".into();
-    singleton.push(buf.pop().ok_or("Might as well write the other digit")?);
-    let y = singleton.parse()?;
-    if !(x >= y && y > 0 && x > 0 && x < 7) {
-        Err("Not a valid dice roll")?;
+    fn start(&mut self) -> BoxResult<MnkGame> {
+        Ok(match self.size {
+            Some((m, n, k)) => MnkGame::with_size(m, n, k)?,
+            None => MnkGame::new(),
+        })
     }
-    Ok((x, y))
 }
 
-fn move_turn(buf: &mut String, s: &Backgammon) -> <Backgammon as GameState>::Move {
-    println!("What's your move?");
-    println!("Legal moves should be");
-    for m in s.legal_moves() {
-        println!("{}", format_sequence(&m.unwrap()))
-    }
-    println!("The format is (location,places moved )+");
-    loop {
-        match move_turn_(buf, s) {
-            Ok(Ok(m)) => return Ok(m),
-            Err(e) => println!("{}", e),
-            _ => panic!("move_turn_ returned illegal move"),
-        }
-    }
+#[derive(Default)]
+struct ConnectFourFrontend {
+    size: Option<(u8, u8)>,
 }
 
-// TODO don't require user to canonicalize
-fn move_turn_(mut buf: &mut String, s: &Backgammon) -> BoxResult<<Backgammon as GameState>::Move> {
-    buf.clear();
-    io::stdin().read_line(&mut buf)?;
-    let m = Ok(parse_moves(buf)?);
-    if !s.legal_moves().contains(&m) {
-        Err("That's not a legal move. Please try again.")?;
+impl Frontend<ConnectFour> for ConnectFourFrontend {
+    fn option(&mut self, name: &str, args: &mut dyn Iterator<Item = String>) -> BoxResult<bool> {
+        if name != "--size" {
+            return Ok(false);
+        }
+        let size = numbers(&value(args, name)?, 2)?;
+        self.size = Some((size[0], size[1]));
+        Ok(true)
     }
-    Ok(m)
-}
 
-fn parse_moves(buf: &str) -> BoxResult<Vec<backgammon::SingleMove>> {
-    let res: BoxResult<Vec<_>> = buf.split_whitespace()
-        .map(|s| {
-            let s = s.replace(",", " ");
-            let ss: Vec<_> = s.split_whitespace().collect();
-            let (l_s, n_s) = (
-                ss.get(0).ok_or("Couldn't get location")?,
-                ss.get(1).ok_or("Couldn't get amount")?,
-            );
-            let l = parse_location(l_s)?;
-            let n = n_s.parse()?;
-            Ok((l, n))
+    fn start(&mut self) -> BoxResult<ConnectFour> {
+        Ok(match self.size {
+            Some((width, height)) => ConnectFour::with_size(width, height)?,
+            None => ConnectFour::new(),
         })
-        .collect();
-    Ok(res?)
+    }
 }
 
-// Should be the inverse of the Display impl
-fn parse_location(s: &str) -> BoxResult<backgammon::Location> {
-    Ok(match s {
-        "home" => Home,
-        "bar" => Bar,
-        s => Board(Point(s.parse()?)),
-    })
+struct BackgammonFrontend {
+    cube: bool,
+    dice: Option<String>,
+    seed: Option<u32>,
+    rolls: Option<Box<dyn Dice>>,
 }
 
-fn computer_turn(
-    gt: &Mutex<MctsTable<Backgammon>>,
-    s: &mut Backgammon,
-) -> BoxResult<<Backgammon as GameState>::Move> {
-    let mut countdown = 5;
-    let moves = s.legal_moves().len();
-    print!("Considering my next move..");
-    io::stdout().flush()?;
-    thread::sleep(Duration::from_millis(2000));
-    while !gt.lock()
-        .unwrap()
-        .0
-        .get(s)
-        .map(|x| (*x).playouts)
-        // constant chosen as a balance between waiting time and strength of play
-        .unwrap_or(0) >= (32 * moves as u32)
-    {
-        if countdown == 0 {
-            break;
-        } else {
-            print!(".");
-            io::stdout().flush()?;
-            countdown -= 1;
-            thread::sleep(Duration::from_millis(1000));
+impl Default for BackgammonFrontend {
+    fn default() -> Self {
+        BackgammonFrontend {
+            cube: false,
+            dice: None,
+            seed: None,
+            rolls: None,
         }
     }
-    println!();
-    Ok(gt.lock()
-        .unwrap()
-        .best_choice(s)
-        .ok_or("No moves available")?)
 }
 
-fn format_sequence(ms: &[backgammon::SingleMove]) -> String {
-    ms.iter()
-        .map(|m| format_move(m))
-        .intersperse(" ".to_string())
-        .collect()
-}
+impl Frontend<Backgammon> for BackgammonFrontend {
+    fn option(&mut self, name: &str, args: &mut dyn Iterator<Item = String>) -> BoxResult<bool> {
+        match name {
+            "--cube" => self.cube = true,
+            "--dice" => self.dice = Some(value(args, name)?),
+            "--seed" => self.seed = Some(value(args, name)?.parse()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 
-fn format_move(m: &backgammon::SingleMove) -> String {
-    let &(l, n) = m;
-    format!("{},{}", l, n)
-}
+    fn start(&mut self) -> BoxResult<Backgammon> {
+        self.rolls = Some(match self.dice.as_ref().map(|d| d.as_str()) {
+            None | Some("random") => {
+                let seed = match self.seed {
+                    Some(seed) => seed,
+                    None => SystemTime::now().duration_since(UNIX_EPOCH)?.subsec_nanos() | 1,
+                };
+                Box::new(RandomDice::new(seed))
+            }
+            Some("manual") => Box::new(ManualDice),
+            Some(path) => Box::new(ReplayDice::load(path)?),
+        });
+        Ok(if self.cube {
+            Backgammon::with_cube()
+        } else {
+            Backgammon::new()
+        })
+    }
 
-// TODO find a better way to format this
-fn print_state(s: &Backgammon) {
-    let mut b = board();
-    b.push(Bar);
-    b.push(Home);
-    println!("The current state should be:");
-    for &l in &b {
-        let count = s.counts[l.into(): usize];
-        println!("{}: ({}, {})", &l, count.0, count.1)
+    // White goes first
+    fn sides(&self) -> Vec<backgammon::Player> {
+        vec![Some(true), Some(false)]
+    }
+
+    fn chance(&mut self) -> Option<Seat<'static, Backgammon>> {
+        self.rolls.take().map(|dice| (None, Box::new(Roller(dice)) as Box<dyn Controller<Backgammon>>))
+    }
+
+    fn finish(&mut self, start: &Backgammon, moves: &[Move], kinds: &[(backgammon::Player, Kind)]) -> BoxResult<()> {
+        println!("To save the game, write a file name ending in .mat or .sgf");
+        let mut buf = String::new();
+        io::stdin().read_line(&mut buf)?;
+        let path = buf.trim();
+        if !path.is_empty() {
+            let name = |p: backgammon::Player| match kinds.iter().find(|&&(q, _)| q == p) {
+                Some(&(_, Kind::Human)) => "Human",
+                _ => "game-trees",
+            };
+            let mut record = GameRecord::new(start.clone());
+            record.moves = moves.to_vec();
+            let mut m = MatchRecord::new(0, name(Some(true)), name(Some(false)));
+            m.games.push(record);
+            m.save(path)?;
+        }
+        Ok(())
     }
 }
 
-fn print_expectations(s: &Backgammon, gt: &Mutex<MctsTable<Backgammon>>) {
-    let meta = &gt.lock().unwrap().0[s];
-    println!(
-        "Expected score {} over {} playouts",
-        meta.scoreboard[&Some(!s.player)] / meta.playouts as f64,
-        meta.playouts
-    );
+// Dice rolled by the person at the keyboard
+struct ManualDice;
+
+impl Dice for ManualDice {
+    fn roll(&mut self) -> io::Result<backgammon::Roll> {
+        println!("What do the dice show?");
+        println!("Write both numbers, such as 6 5");
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if io::stdin().read_line(&mut buf)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "No more input"));
+            }
+            match notation::parse_roll(&buf) {
+                Ok(roll) => return Ok(roll),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
 }
@@ -1,11 +1,15 @@
-extern crate rand;
 extern crate fnv;
 
-use game::{GameState, ScoreBoard};
+use game::{Evaluator, GameState, Score, ScoreBoard};
 use self::fnv::FnvHashMap;
-use self::rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng, XorShiftRng};
 use std::f64;
 use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+#[cfg(feature = "debug")]
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[derive(Default, Clone)]
@@ -40,10 +44,91 @@ fn all_scores_zero<G: GameState>() -> ScoreBoard<G> {
     G::players().into_iter().map(|p| (p, 0.0)).collect()
 }
 
+// A playout in three steps, for tables shared between threads to be locked only
+// while descending them and backing up, not while the game is played out
+pub struct Descent<G: GameState> {
+    // States of the table passed through, from the root down
+    path: Vec<G>,
+    // Where the game goes on outside the table, with the iterations left
+    rest: Option<(G, u32)>,
+    // States met outside the table
+    expanded: Vec<(G, Meta<G>)>,
+    scores: Option<ScoreBoard<G>>,
+}
+
+impl<G: GameState> Descent<G> {
+    // Plays the game out from where the descent left the table, without the table
+    pub fn play_out<R: Rng>(&mut self, evaluator: Option<&Evaluator<G>>, rng: &mut R) {
+        let end_scores = |s: &G| {
+            s.scores()
+                .or_else(|| evaluator.and_then(|e| e.evaluate(s)))
+                .unwrap_or_else(all_scores_zero::<G>)
+        };
+        let (mut s, mut its) = match self.rest.take() {
+            Some(rest) => rest,
+            None => {
+                self.scores = self.path.last().map(&end_scores);
+                return;
+            }
+        };
+        loop {
+            let mut meta = Meta::with_state(s.clone());
+            if let Some(scores) = evaluator.and_then(|e| e.evaluate(&s)) {
+                self.expanded.push((s, meta));
+                self.scores = Some(scores);
+                return;
+            }
+            let moves: Vec<_> = meta.moves.keys().cloned().collect();
+            match rng.choose(&moves) {
+                Some(m) if its > 0 => {
+                    let new = {
+                        let entry = meta.moves.get_mut(m).unwrap();
+                        entry.1 += 1;
+                        entry.0.clone()
+                    };
+                    self.expanded.push((s, meta));
+                    s = new;
+                    its -= 1;
+                }
+                _ => {
+                    self.scores = Some(end_scores(&s));
+                    self.expanded.push((s, meta));
+                    return;
+                }
+            }
+        }
+    }
+}
+
 // DISCUSS include a field for the current state?
-#[cfg_attr(feature = "debug", derive(Debug))]
-#[derive(Default)]
-pub struct MctsTable<G: GameState + Clone>(pub FnvHashMap<G, Meta<G>>);
+pub struct MctsTable<G: GameState + Clone> {
+    pub nodes: FnvHashMap<G, Meta<G>>,
+    // Consulted for new states, whose playouts stop there if it knows them
+    evaluator: Option<Arc<Evaluator<G>>>,
+    // Breaks ties, the same seed gives the same search
+    rng: XorShiftRng,
+    // Weight of exploring little visited moves against exploiting good ones
+    exploration: f64,
+}
+
+impl<G: GameState> Default for MctsTable<G> {
+    fn default() -> Self {
+        MctsTable {
+            nodes: FnvHashMap::default(),
+            evaluator: None,
+            rng: XorShiftRng::new_unseeded(),
+            exploration: f64::consts::SQRT_2,
+        }
+    }
+}
+
+// The evaluator can't be printed
+#[cfg(feature = "debug")]
+impl<G: GameState + fmt::Debug> fmt::Debug for MctsTable<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MctsTable").field("nodes", &self.nodes).finish()
+    }
+}
 
 impl<G: GameState> MctsTable<G> {
     pub fn new() -> Self {
@@ -51,22 +136,43 @@ impl<G: GameState> MctsTable<G> {
     }
 
     pub fn with_state(s: G) -> Self {
-        let mut table = MctsTable(FnvHashMap::default());
+        let mut table = Self::default();
+        table.insert(s);
+        table
+    }
+
+    pub fn with_evaluator(s: G, evaluator: Arc<Evaluator<G>>) -> Self {
+        let mut table = Self::default();
+        table.evaluator = Some(evaluator);
         table.insert(s);
         table
     }
 
+    // Tables start out with the same fixed seed
+    pub fn seed(&mut self, seed: [u32; 4]) {
+        self.rng = XorShiftRng::from_seed(seed);
+    }
+
+    pub fn evaluator(&self) -> Option<Arc<Evaluator<G>>> {
+        self.evaluator.clone()
+    }
+
+    // The constant of UCB1, the square root of 2 unless set
+    pub fn set_exploration(&mut self, c: f64) {
+        self.exploration = c;
+    }
+
     fn insert(&mut self, s: G) {
-        self.0.insert(s.clone(), Meta::with_state(s));
+        self.nodes.insert(s.clone(), Meta::with_state(s));
     }
 
     // Most robust move
     pub fn best_choice(&self, s: &G) -> Option<G::Move> {
-        self.0.get(s).and_then(|meta| {
+        self.nodes.get(s).and_then(|meta| {
             meta.moves
                 .iter()
                 .max_by_key(|&(_, new)| {
-                    self.0
+                    self.nodes
                         .get(&new.0)
                         .map(|new_meta| new_meta.playouts)
                         .unwrap_or(0)
@@ -75,22 +181,42 @@ impl<G: GameState> MctsTable<G> {
         })
     }
 
+    // The moves tried in the state, most robust first,
+    // with their playouts and the score the player making them may expect
+    pub fn ranked_moves(&self, s: &G) -> Vec<(G::Move, u32, Score)> {
+        let p = s.current_player();
+        let mut moves: Vec<_> = self.nodes.get(s).map_or(Vec::new(), |meta| {
+            meta.moves
+                .iter()
+                .filter_map(|(m, new)| self.nodes.get(&new.0).map(|new_meta| (m, new_meta)))
+                .filter(|&(_, new_meta)| new_meta.playouts > 0)
+                .map(|(m, new_meta)| {
+                    let score = new_meta.scoreboard[&p] / new_meta.playouts as f64;
+                    (m.clone(), new_meta.playouts, score)
+                })
+                .collect()
+        });
+        moves.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal)));
+        moves
+    }
+
     // move with highest upper confidence bound (UCB1)
-    fn best_choice_(&self, s: &G) -> Option<G::Move> {
-        self.0.get(s).and_then(|meta| {
+    fn best_choice_(&mut self, s: &G) -> Option<G::Move> {
+        let c = self.exploration;
+        let bests = self.nodes.get(s).map(|meta| {
             // Hopefully keeping `moves` as a "raw" iterator
             // should fuse it with bests
             // otherwise the variable should be eliminated manually
             // TODO bench this
             let moves = meta.moves.iter().map(|(m, new)| {
-                let weight = match self.0.get(&new.0) {
+                let weight = match self.nodes.get(&new.0) {
                     None => f64::INFINITY,
                     Some(v) => {
                         if v.playouts == 0 {
                             f64::INFINITY
                         } else {
                             v.scoreboard[&s.current_player()] / (v.playouts as f64) +
-                                f64::sqrt(2.0 * (meta.playouts as f64).ln() / (v.playouts as f64))
+                                c * f64::sqrt((meta.playouts as f64).ln() / (v.playouts as f64))
                         }
                     }
                 };
@@ -98,7 +224,7 @@ impl<G: GameState> MctsTable<G> {
             });
             // TODO is this better or worse than finding the best score first
             // then only retaining those with the best score?
-            let bests = moves
+            moves
                 .fold((Vec::new(), f64::NEG_INFINITY), |(mut ms, mut best),
                  (m, weight)| {
                     match weight.partial_cmp(&best) {
@@ -111,11 +237,9 @@ impl<G: GameState> MctsTable<G> {
                     }
                     (ms, best)
                 })
-                .0;
-            // TODO is this an appropriate source of randomness
-            // for throw-away (one-time) thread-local use?
-            thread_rng().choose(&bests).map(|m| (*m).clone())
-        })
+                .0
+        });
+        bests.and_then(|bests| self.rng.choose(&bests).cloned())
     }
 
     // DISCUSS just return the scoreboard from each playout
@@ -125,35 +249,43 @@ impl<G: GameState> MctsTable<G> {
     }
 
     fn playout_(&mut self, s: &G, max_its: u32) -> ScoreBoard<G> {
-        if self.0.get(s).is_none() {
+        let mut evaluation = None;
+        if self.nodes.get(s).is_none() {
             self.insert(s.clone());
+            evaluation = self.evaluator.as_ref().and_then(|e| e.evaluate(s));
         }
         // Can't match here,
         // there'd be an immutable borrow of s active in the Some arm
         // preventing updating on the way back up the "tree"
-        if self.0.get(s).is_some() {
-            let best_move_opt = if max_its > 0 {
+        if self.nodes.get(s).is_some() {
+            let best_move_opt = if max_its > 0 && evaluation.is_none() {
                 self.best_choice_(s)
             } else {
                 None
             };
             let scores: ScoreBoard<G>;
-            match best_move_opt {
-                Some(best_move) => {
+            match (evaluation, best_move_opt) {
+                (Some(evaluation), _) => {
+                    scores = evaluation;
+                }
+                (None, Some(best_move)) => {
                     let mut new = s.clone();
                     {
-                        let v = self.0.get_mut(s).unwrap();
+                        let v = self.nodes.get_mut(s).unwrap();
                         v.moves.get_mut(&best_move).unwrap().1 += 1;
                     }
                     new.apply(best_move);
                     // TODO make this iterative rather than recursive
                     scores = self.playout_(&new, max_its - 1);
                 }
-                None => {
-                    scores = s.scores().unwrap_or_else(all_scores_zero::<G>);
+                (None, None) => {
+                    let evaluator = &self.evaluator;
+                    scores = s.scores()
+                        .or_else(|| evaluator.as_ref().and_then(|e| e.evaluate(s)))
+                        .unwrap_or_else(all_scores_zero::<G>);
                 }
             }
-            let mut v = self.0.get_mut(s).unwrap();
+            let mut v = self.nodes.get_mut(s).unwrap();
             v.paths += 1;
             v.playouts += 1;
             for (key, score) in &mut v.scoreboard {
@@ -165,6 +297,75 @@ impl<G: GameState> MctsTable<G> {
         }
     }
 
+    // Follows the best moves while the states are in the table.
+    // Paths are counted on the way down, for the table to stay consistent
+    // should it be garbage collected before the playout is backed up
+    pub fn descend(&mut self, s: &G, max_its: u32) -> Descent<G> {
+        let mut path = Vec::new();
+        let mut s = s.clone();
+        let mut its = max_its;
+        let mut rest = None;
+        loop {
+            if !self.nodes.contains_key(&s) {
+                rest = Some((s, its));
+                break;
+            }
+            self.nodes.get_mut(&s).unwrap().paths += 1;
+            path.push(s.clone());
+            let best_move_opt = if its > 0 { self.best_choice_(&s) } else { None };
+            match best_move_opt {
+                Some(best_move) => {
+                    self.nodes.get_mut(&s).unwrap().moves.get_mut(&best_move).unwrap().1 += 1;
+                    s.apply(best_move);
+                    its -= 1;
+                }
+                None => break,
+            }
+        }
+        Descent {
+            path: path,
+            rest: rest,
+            expanded: Vec::new(),
+            scores: None,
+        }
+    }
+
+    // Adds the scores of a played out descent to the states it went through.
+    // States gone from the table since are left out, as are those met after them
+    pub fn back_up(&mut self, descent: Descent<G>) {
+        let scores = descent.scores.unwrap_or_else(all_scores_zero::<G>);
+        let add = |meta: &mut Meta<G>| {
+            meta.playouts += 1;
+            for (key, score) in &mut meta.scoreboard {
+                *score += scores[key]
+            }
+        };
+        let mut connected = true;
+        for s in &descent.path {
+            match self.nodes.get_mut(s) {
+                Some(meta) => add(meta),
+                None => connected = false,
+            }
+        }
+        if !connected {
+            return;
+        }
+        for (s, new_meta) in descent.expanded {
+            let meta = match self.nodes.entry(s) {
+                Entry::Occupied(entry) => {
+                    let meta = entry.into_mut();
+                    for (m, &(_, touches)) in &new_meta.moves {
+                        meta.moves.get_mut(m).unwrap().1 += touches;
+                    }
+                    meta
+                }
+                Entry::Vacant(entry) => entry.insert(new_meta),
+            };
+            meta.paths += 1;
+            add(meta);
+        }
+    }
+
     // TODO merge this with the code from main
     // to specify a "to" and "from" state
     // where the "from" state is deleted and its children collected
@@ -175,14 +376,14 @@ impl<G: GameState> MctsTable<G> {
         let mut initial = true;
         while !to_be_gced.is_empty() {
             let curr = to_be_gced.pop().unwrap();
-            let exists = self.0.get(&curr).is_some();
+            let exists = self.nodes.get(&curr).is_some();
             if exists {
-                let old_meta = self.0[&curr].clone();
+                let old_meta = self.nodes[&curr].clone();
                 if old_meta.paths == 0 || initial {
-                    self.0.remove(&curr);
+                    self.nodes.remove(&curr);
                     for (_, (new, touches)) in old_meta.moves {
                         if touches > 0 {
-                            self.0.get_mut(&new).map(|meta| meta.paths -= touches);
+                            self.nodes.get_mut(&new).map(|meta| meta.paths -= touches);
                             to_be_gced.push(new);
                         }
                     }
@@ -191,4 +392,131 @@ impl<G: GameState> MctsTable<G> {
             initial = false;
         }
     }
+
+    // What was learnt about the states reachable from `root`, a line per state with `root` first.
+    // A line has the playouts and paths through the state, the scores in the order of `G::players()`,
+    // then "i:j:n" for each move tried, the move being the ith of `possible_moves`
+    // and leading to the state on line j, tried n times
+    pub fn write_nodes(&self, root: &G) -> Vec<String> {
+        let mut ids = FnvHashMap::default();
+        let mut order = Vec::new();
+        if self.nodes.contains_key(root) {
+            ids.insert(root.clone(), 0);
+            order.push(root.clone());
+        }
+        let mut lines = Vec::new();
+        let mut i = 0;
+        while i < order.len() {
+            let s = order[i].clone();
+            let meta = &self.nodes[&s];
+            let mut fields = vec![meta.playouts.to_string(), meta.paths.to_string()];
+            fields.extend(G::players().iter().map(|p| meta.scoreboard[p].to_string()));
+            for (j, m) in s.possible_moves().into_iter().enumerate() {
+                let (ref new, touches) = meta.moves[&m];
+                if self.nodes.contains_key(new) {
+                    let next = order.len();
+                    let id = *ids.entry(new.clone()).or_insert(next);
+                    if id == next {
+                        order.push(new.clone());
+                    }
+                    fields.push(format!("{}:{}:{}", j, id, touches));
+                }
+            }
+            lines.push(fields.join(" "));
+            i += 1;
+        }
+        lines
+    }
+
+    // Replaces the states in the table with those written by `write_nodes`
+    pub fn read_nodes(&mut self, root: &G, lines: &[String]) -> Result<(), String> {
+        let mut states = vec![Some(root.clone())];
+        let mut table = FnvHashMap::default();
+        for (i, line) in lines.iter().enumerate() {
+            let s = states
+                .get(i)
+                .and_then(|s| s.clone())
+                .ok_or(format!("No move leads to state {}", i))?;
+            let mut fields = line.split_whitespace();
+            let mut meta = Meta::with_state(s.clone());
+            meta.playouts = field(fields.next(), "playouts")?;
+            meta.paths = field(fields.next(), "paths")?;
+            for p in G::players() {
+                meta.scoreboard.insert(p, field(fields.next(), "score")?);
+            }
+            let moves = s.possible_moves();
+            for edge in fields {
+                let numbers = edge.split(':').collect::<Vec<_>>();
+                if numbers.len() != 3 {
+                    Err(format!("Expected a move such as 3:12:40, found {}", edge))?;
+                }
+                let m: usize = field(Some(numbers[0]), "move")?;
+                let j: usize = field(Some(numbers[1]), "state")?;
+                let m = moves.get(m).ok_or(format!("State {} has no move {}", i, m))?;
+                let entry = meta.moves.get_mut(m).unwrap();
+                entry.1 = field(Some(numbers[2]), "tries")?;
+                if states.len() <= j {
+                    states.resize(j + 1, None);
+                }
+                states[j] = Some(entry.0.clone());
+            }
+            table.insert(s, meta);
+        }
+        if !table.contains_key(root) {
+            table.insert(root.clone(), Meta::with_state(root.clone()));
+        }
+        self.nodes = table;
+        Ok(())
+    }
+}
+
+fn field<T: FromStr>(text: Option<&str>, name: &str) -> Result<T, String> {
+    text.ok_or(format!("Missing {}", name))?
+        .parse()
+        .map_err(|_| format!("Bad {} {}", name, text.unwrap_or("")))
+}
+
+// Judges a state by searching it with a table of its own,
+// which makes search usable wherever an evaluator is
+pub struct SearchEvaluator<G: GameState> {
+    pub playouts: u32,
+    pub max_its: u32,
+    evaluator: Option<Arc<Evaluator<G>>>,
 }
+
+impl<G: GameState> SearchEvaluator<G> {
+    pub fn new(playouts: u32, max_its: u32) -> Self {
+        SearchEvaluator {
+            playouts: playouts,
+            max_its: max_its,
+            evaluator: None,
+        }
+    }
+
+    pub fn with_evaluator(playouts: u32, max_its: u32, evaluator: Arc<Evaluator<G>>) -> Self {
+        SearchEvaluator {
+            playouts: playouts,
+            max_its: max_its,
+            evaluator: Some(evaluator),
+        }
+    }
+}
+
+impl<G: GameState> Evaluator<G> for SearchEvaluator<G> {
+    fn evaluate(&self, s: &G) -> Option<ScoreBoard<G>> {
+        if let Some(scores) = s.scores() {
+            return Some(scores);
+        }
+        let mut table = match self.evaluator {
+            Some(ref e) => MctsTable::with_evaluator(s.clone(), e.clone()),
+            None => MctsTable::with_state(s.clone()),
+        };
+        for _ in 0..self.playouts {
+            table.playout(s, self.max_its);
+        }
+        let meta = &table.nodes[s];
+        let playouts = meta.playouts.max(1) as f64;
+        Some(meta.scoreboard.iter().map(|(p, score)| (p.clone(), score / playouts)).collect())
+    }
+}
+
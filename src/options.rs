@@ -0,0 +1,128 @@
+// Command line options of the binary, other than those only some games understand
+use arena::EngineConfig;
+use game::GameState;
+use play::Kind;
+use std::f64;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Mode {
+    HumanEngine,
+    EngineEngine,
+    HumanHuman,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Options {
+    pub mode: Mode,
+    pub human_first: bool,
+    // None to wait for a number depending on the legal moves
+    pub playouts: Option<u32>,
+    pub time: Duration,
+    pub threads: usize,
+    pub exploration: f64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            mode: Mode::HumanEngine,
+            human_first: true,
+            playouts: None,
+            time: Duration::from_secs(7),
+            threads: 1,
+            exploration: f64::consts::SQRT_2,
+        }
+    }
+}
+
+impl Options {
+    // Options not known here are offered to `other`, which reads any values they have
+    // and tells whether it knew the option
+    pub fn parse<F>(args: &mut dyn Iterator<Item = String>, mut other: F) -> Result<Self, String>
+    where
+        F: FnMut(&str, &mut dyn Iterator<Item = String>) -> Result<bool, String>,
+    {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--mode" => {
+                    options.mode = match value(args, &arg)?.as_str() {
+                        "human-engine" => Mode::HumanEngine,
+                        "engine-engine" => Mode::EngineEngine,
+                        "human-human" => Mode::HumanHuman,
+                        mode => Err(format!("Unknown mode {}", mode))?,
+                    }
+                }
+                "--first" => {
+                    options.human_first = match value(args, &arg)?.as_str() {
+                        "human" => true,
+                        "engine" => false,
+                        side => Err(format!("Unknown side {}", side))?,
+                    }
+                }
+                "--playouts" => options.playouts = Some(parsed_value(args, &arg)?),
+                "--time" => {
+                    let seconds: f64 = parsed_value(args, &arg)?;
+                    options.time = Duration::from_millis((seconds * 1000.0) as u64);
+                }
+                "--threads" => options.threads = parsed_value(args, &arg)?,
+                "--exploration" => options.exploration = parsed_value(args, &arg)?,
+                _ => {
+                    if !other(&arg, args)? {
+                        Err(format!("Unknown option {}", arg))?;
+                    }
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    // Who makes the choices for each of the players, in the order they move
+    pub fn kinds<G: GameState>(&self, sides: Vec<G::Player>) -> Vec<(G::Player, Kind)> {
+        sides
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let c = match self.mode {
+                    Mode::HumanEngine if (i == 0) == self.human_first => Kind::Human,
+                    Mode::HumanEngine => Kind::Engine,
+                    Mode::EngineEngine => Kind::Engine,
+                    Mode::HumanHuman => Kind::Human,
+                };
+                (p, c)
+            })
+            .collect()
+    }
+}
+
+// The value following the option `name`
+pub fn value(args: &mut dyn Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{} needs a value", name))
+}
+
+pub fn parsed_value<T: FromStr>(args: &mut dyn Iterator<Item = String>, name: &str) -> Result<T, String> {
+    let text = value(args, name)?;
+    text.parse().map_err(|_| format!("Bad value for {}: {}", name, text))
+}
+
+// Settings of the nth engine of a tournament, such as "name=wide,playouts=800,exploration=2,max-its=400"
+pub fn engine_config<G: GameState>(config: &str, n: usize) -> Result<EngineConfig<G>, String> {
+    let mut e = EngineConfig::new(&format!("engine {}", n), 400);
+    for setting in config.split(',') {
+        let mut parts = setting.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("name"), Some(name)) => e.name = name.to_string(),
+            (Some("playouts"), Some(n)) => e.playouts = setting_value(setting, n)?,
+            (Some("max-its"), Some(n)) => e.max_its = setting_value(setting, n)?,
+            (Some("exploration"), Some(c)) => e.exploration = setting_value(setting, c)?,
+            _ => Err(format!("Unknown engine setting {}", setting))?,
+        }
+    }
+    Ok(e)
+}
+
+fn setting_value<T: FromStr>(setting: &str, text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("Bad engine setting {}", setting))
+}
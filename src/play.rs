@@ -0,0 +1,473 @@
+// Playing games in the terminal, with people, engines and dice taking the seats.
+// A game needs its notation and a way of rendering it to be played here
+use engine::{Budget, Engine};
+use game::{GameState, Notation, Score};
+use mcts_hashtable::MctsTable;
+use saved::SavedGame;
+use std::io;
+use std::io::Write;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+
+// Moves shown when asking for a hint without saying how many
+const HINTS: usize = 5;
+const ANALYSIS_INTERVAL: u64 = 1000;
+
+// Showing a game to people
+pub trait Render: Notation {
+    fn render(&self) -> String;
+    fn player_name(p: &Self::Player) -> String;
+    // Shown to people about to move
+    fn move_help() -> String;
+    // What a winning score amounts to, such as "2 points", in games that are won by more than a win
+    fn winnings(_score: Score) -> Option<String> {
+        None
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Kind {
+    Human,
+    Engine,
+    // Such as the dice, which nobody chooses
+    Chance,
+}
+
+// What people at the keyboard may ask for instead of moving
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Command {
+    // Shows the moves the engine likes best
+    Hint(usize),
+    // Shows them again and again as the engine learns more, until Enter is pressed
+    Analyze,
+    // Takes back moves until a person is to move again
+    Undo,
+    // Makes the moves taken back again
+    Redo,
+    History,
+    // Goes to the position after that many moves, 0 being the start
+    Goto(usize),
+    // Saves the game to the file, to be resumed later
+    Save(String),
+    Quit,
+}
+
+impl Command {
+    // None when the text isn't a command, so may be a move
+    pub fn parse(text: &str) -> Option<Result<Command, String>> {
+        let text = text.trim();
+        let (word, rest) = match text.find(char::is_whitespace) {
+            Some(i) => (&text[..i], text[i..].trim()),
+            None => (text, ""),
+        };
+        Some(match word {
+            "hint" if rest.is_empty() => Ok(Command::Hint(HINTS)),
+            "hint" => rest.parse()
+                .map(Command::Hint)
+                .map_err(|_| "Write the number of moves to show, such as hint 3".to_string()),
+            "analyze" => Ok(Command::Analyze),
+            "undo" => Ok(Command::Undo),
+            "redo" => Ok(Command::Redo),
+            "history" => Ok(Command::History),
+            "goto" => rest.parse()
+                .map(Command::Goto)
+                .map_err(|_| "Write the number of moves to go to, such as goto 12".to_string()),
+            "save" if rest.is_empty() => Err("Save to which file?".to_string()),
+            "save" => Ok(Command::Save(rest.to_string())),
+            "quit" => Ok(Command::Quit),
+            _ => return None,
+        })
+    }
+
+    pub fn help() -> &'static str {
+        "Or write hint [n] or analyze to ask the engine, undo, redo, history, goto <n>,\n\
+         save <file> to save the game for later, or quit"
+    }
+}
+
+pub enum Choice<M> {
+    Move(M),
+    Command(Command),
+}
+
+// Whoever makes the choices for a player
+pub trait Controller<G: GameState> {
+    fn kind(&self) -> Kind;
+    fn choose(&mut self, s: &G) -> io::Result<Choice<G::Move>>;
+}
+
+// Someone at the keyboard
+pub struct Human;
+
+impl<G: Render> Controller<G> for Human {
+    fn kind(&self) -> Kind {
+        Kind::Human
+    }
+
+    fn choose(&mut self, s: &G) -> io::Result<Choice<G::Move>> {
+        println!("What's your move?");
+        println!("Legal moves should be");
+        for m in s.legal_moves() {
+            println!("{}", s.format_move(&m))
+        }
+        println!("{}", G::move_help());
+        println!("{}", Command::help());
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if io::stdin().read_line(&mut buf)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "No more input"));
+            }
+            let choice = match Command::parse(&buf) {
+                Some(command) => command.map(Choice::Command),
+                None => s.parse_move(&buf).map(Choice::Move),
+            };
+            match choice {
+                Ok(choice) => return Ok(choice),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+}
+
+// Thinks until the position has been played out `playouts` times or the time is up,
+// 32 playouts per legal move unless told otherwise
+pub struct EnginePlayer<'a, G: GameState + 'a> {
+    engine: &'a Engine<G>,
+    pub playouts: Option<u32>,
+    pub time: Duration,
+}
+
+impl<'a, G: GameState + 'static> EnginePlayer<'a, G> {
+    pub fn new(engine: &'a Engine<G>) -> Self {
+        EnginePlayer {
+            engine: engine,
+            playouts: None,
+            time: Duration::from_secs(7),
+        }
+    }
+}
+
+impl<'a, G: GameState + 'static> Controller<G> for EnginePlayer<'a, G> {
+    fn kind(&self) -> Kind {
+        Kind::Engine
+    }
+
+    fn choose(&mut self, s: &G) -> io::Result<Choice<G::Move>> {
+        // constant chosen as a balance between waiting time and strength of play
+        let budget = Budget {
+            playouts: Some(self.playouts.unwrap_or(32 * s.legal_moves().len() as u32)),
+            time: Some(self.time),
+        };
+        print!("Considering my next move...");
+        io::stdout().flush()?;
+        let m = self.engine.best_move(budget);
+        println!();
+        m.map(Choice::Move)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "No moves available"))
+    }
+}
+
+pub type Seat<'a, G> = (<G as GameState>::Player, Box<Controller<G> + 'a>);
+
+// A game in progress: where it started and the moves since,
+// with the command line it was started with to resume it by
+pub struct Session<G: GameState> {
+    pub start: G,
+    pub moves: Vec<G::Move>,
+    // Moves taken back, the next to redo last
+    pub undone: Vec<G::Move>,
+    pub args: Vec<String>,
+}
+
+impl<G: Notation + 'static> Session<G> {
+    pub fn new(start: G, args: Vec<String>) -> Self {
+        Session {
+            start: start,
+            moves: Vec::new(),
+            undone: Vec::new(),
+            args: args,
+        }
+    }
+
+    // Continues a saved game, `start` being where it started
+    pub fn resume(saved: &SavedGame, start: G) -> Result<Self, String> {
+        let (moves, _) = saved.replay(&start)?;
+        Ok(Session {
+            start: start,
+            moves: moves,
+            undone: Vec::new(),
+            args: saved.args.clone(),
+        })
+    }
+
+    pub fn position(&self) -> G {
+        let mut s = self.start.clone();
+        for m in &self.moves {
+            s.apply(m.clone());
+        }
+        s
+    }
+
+    // Moves other than the next to redo make the ones taken back unreachable
+    pub fn push(&mut self, m: G::Move) {
+        if self.undone.last() == Some(&m) {
+            self.undone.pop();
+        } else {
+            self.undone.clear();
+        }
+        self.moves.push(m);
+    }
+
+    // False at the start
+    pub fn undo(&mut self) -> bool {
+        match self.moves.pop() {
+            Some(m) => {
+                self.undone.push(m);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // False when no moves were taken back
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(m) => {
+                self.moves.push(m);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The position after n moves, counting those taken back
+    pub fn goto(&mut self, n: usize) -> Result<(), String> {
+        if n > self.moves.len() + self.undone.len() {
+            Err(format!("There are only {} moves", self.moves.len() + self.undone.len()))?;
+        }
+        while self.moves.len() > n {
+            self.undo();
+        }
+        while self.moves.len() < n {
+            self.redo();
+        }
+        Ok(())
+    }
+
+    // Numbered moves with who made them, those taken back marked
+    pub fn history(&self) -> Vec<String>
+    where
+        G: Render,
+    {
+        let mut s = self.start.clone();
+        let mut lines = Vec::new();
+        for (i, m) in self.moves.iter().chain(self.undone.iter().rev()).enumerate() {
+            lines.push(format!(
+                "{}. {}: {}{}",
+                i + 1,
+                G::player_name(&s.current_player()),
+                s.format_move(m),
+                if i < self.moves.len() { "" } else { " (taken back)" }
+            ));
+            s.apply(m.clone());
+        }
+        lines
+    }
+
+    // Saves what the engine learnt too, if there is one following the game
+    pub fn save(&self, path: &str, engine: Option<&Engine<G>>) -> io::Result<()> {
+        let table = engine.map(|engine| engine.table());
+        SavedGame::new(&self.args, &self.start, &self.moves, table.as_ref().map(|table| &**table)).save(path)
+    }
+}
+
+// Plays the game on until it ends or somebody quits.
+// The engine, if any, follows every move
+pub fn play<'a, G>(session: &mut Session<G>, seats: &mut [Seat<'a, G>], engine: Option<&Engine<G>>) -> io::Result<()>
+where
+    G: Render + 'static,
+{
+    let kinds: Vec<_> = seats.iter().map(|seat| (seat.0.clone(), seat.1.kind())).collect();
+    let humans = kinds.iter().filter(|&&(_, kind)| kind == Kind::Human).count();
+    let human_turn = |s: &G| kinds.iter().any(|&(ref p, kind)| *p == s.current_player() && kind == Kind::Human);
+    let mut s = session.position();
+    println!("{}", s.render());
+    while !s.finished() {
+        let mover = s.current_player();
+        let seat = seats.iter_mut().find(|seat| seat.0 == mover).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, format!("Nobody is playing {}", G::player_name(&mover)))
+        })?;
+        let kind = seat.1.kind();
+        if kind == Kind::Human && humans > 1 {
+            println!("{} to move", G::player_name(&mover));
+        }
+        let m = match seat.1.choose(&s)? {
+            Choice::Move(m) => m,
+            Choice::Command(Command::Hint(n)) => {
+                match engine {
+                    Some(engine) => print_hints(&s, engine, n),
+                    None => println!("There is no engine to ask"),
+                }
+                continue;
+            }
+            Choice::Command(Command::Analyze) => {
+                match engine {
+                    Some(engine) => analyze(&s, engine)?,
+                    None => println!("There is no engine to ask"),
+                }
+                continue;
+            }
+            Choice::Command(Command::Undo) => {
+                let n = session.moves.len();
+                while session.undo() && !human_turn(&session.position()) {}
+                if !human_turn(&session.position()) || session.moves.len() == n {
+                    while session.moves.len() < n && session.redo() {}
+                    println!("There is nothing to take back");
+                } else {
+                    s = moved_to(session, engine);
+                }
+                continue;
+            }
+            Choice::Command(Command::Redo) => {
+                if !session.redo() {
+                    println!("There is nothing to redo");
+                    continue;
+                }
+                while !human_turn(&session.position()) && session.redo() {}
+                s = moved_to(session, engine);
+                continue;
+            }
+            Choice::Command(Command::Goto(n)) => {
+                match session.goto(n) {
+                    Ok(()) => s = moved_to(session, engine),
+                    Err(e) => println!("{}", e),
+                }
+                continue;
+            }
+            Choice::Command(Command::History) => {
+                for line in session.history() {
+                    println!("{}", line);
+                }
+                continue;
+            }
+            Choice::Command(Command::Save(path)) => {
+                match session.save(&path, engine) {
+                    Ok(()) => println!("Saved the game to {}", path),
+                    Err(e) => println!("Couldn't save the game to {}: {}", path, e),
+                }
+                continue;
+            }
+            Choice::Command(Command::Quit) => return Ok(()),
+        };
+        if kind == Kind::Engine {
+            if humans == 1 {
+                println!("My move is: {}", s.format_move(&m));
+            } else {
+                println!("{} plays: {}", G::player_name(&mover), s.format_move(&m));
+            }
+        }
+        session.push(m.clone());
+        if let Some(engine) = engine {
+            engine.play(m.clone());
+        }
+        s.apply(m);
+        if !s.finished() && kind != Kind::Chance {
+            if let Some(engine) = engine {
+                print_expectations(&s, &mover, &*engine.table());
+            }
+            println!("{}", s.render());
+        }
+    }
+    announce(&s, seats, humans);
+    Ok(())
+}
+
+fn print_hints<G: Notation + 'static>(s: &G, engine: &Engine<G>, n: usize) {
+    let table = engine.table();
+    let moves = table.ranked_moves(s);
+    if moves.is_empty() {
+        println!("The engine hasn't tried any moves yet");
+        return;
+    }
+    println!("After {} playouts:", table.nodes.get(s).map_or(0, |meta| meta.playouts));
+    for (m, playouts, score) in moves.into_iter().take(n) {
+        println!("  {:<24} {:>9} playouts, expected score {:.3}", s.format_move(&m), playouts, score);
+    }
+}
+
+// The engine searches the position meanwhile, as it does whenever a person is to move
+fn analyze<G: Notation + 'static>(s: &G, engine: &Engine<G>) -> io::Result<()> {
+    println!("Press Enter to stop analyzing");
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = tx.send(io::stdin().read_line(&mut buf));
+    });
+    loop {
+        print_hints(s, engine, HINTS);
+        println!();
+        match rx.recv_timeout(Duration::from_millis(ANALYSIS_INTERVAL)) {
+            Ok(read) => return read.map(|_| ()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+// After going back or forth in the game
+fn moved_to<G: Render + 'static>(session: &Session<G>, engine: Option<&Engine<G>>) -> G {
+    let s = session.position();
+    if let Some(engine) = engine {
+        engine.ponder(s.clone());
+    }
+    println!("{}", s.render());
+    s
+}
+
+fn announce<G: Render>(s: &G, seats: &[Seat<G>], humans: usize) {
+    let scores = match s.scores() {
+        Some(scores) => scores,
+        None => return,
+    };
+    let won = |score: Score| G::winnings(score).map_or(String::new(), |w| format!(" {}", w));
+    let engine = seats.iter().find(|seat| seat.1.kind() == Kind::Engine);
+    match (humans, engine) {
+        (1, Some(&(ref e, _))) => {
+            let points = -scores[e];
+            if points > 0.0 {
+                println!("Looks like you won{}. Congratulations!", won(points));
+            } else if points < 0.0 {
+                println!("Looks like I won{}. Too bad!", won(-points));
+            } else {
+                println!("Looks like a draw.");
+            }
+        }
+        _ => {
+            let winners: Vec<_> = seats
+                .iter()
+                .filter(|&&(ref p, ref c)| c.kind() != Kind::Chance && scores[p] > 0.0)
+                .collect();
+            for &&(ref p, _) in &winners {
+                println!("{} won{}", G::player_name(p), won(scores[p]));
+            }
+            if winners.is_empty() {
+                println!("The game is a draw");
+            }
+        }
+    }
+}
+
+fn print_expectations<G: GameState>(s: &G, mover: &G::Player, table: &MctsTable<G>) {
+    if let Some(meta) = table.nodes.get(s) {
+        if meta.playouts > 0 {
+            println!(
+                "Expected score {} over {} playouts",
+                meta.scoreboard[mover] / meta.playouts as f64,
+                meta.playouts
+            );
+        }
+    }
+}
@@ -0,0 +1,231 @@
+// A line based protocol for driving the engine from other programs, much like UCI.
+//
+//     position start | position <position>   set the position
+//     move <move>                            play a move in the current position
+//     dice <roll>                            roll the dice, in games with dice
+//     go [playouts <n>] [time <ms>]          search, until stopped if no budget is given.
+//                                            Playouts count those made in earlier searches of the position
+//     stop                                   end the search
+//     show                                   answered with the current position
+//     legal                                  answered with the legal moves, one per line
+//     isready                                answered with readyok
+//     quit
+//
+// A search reports lines such as "info playouts 4096 nodes 9000 time 512 score 0.25 pv 8/5 6/5 31 13/10"
+// every now and then, the score being expected for the player to move, and ends with "bestmove <move>".
+// Any command but show, legal and isready stops the search first.
+// Failed commands are answered with "error <message>"
+use engine::{Budget, Engine, EngineSettings};
+use game::Codec;
+use mcts_hashtable::MctsTable;
+use std::io;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const INFO_INTERVAL: u64 = 500;
+const PV_LENGTH: usize = 12;
+
+// "playouts <n>" and "time <milliseconds>" in any order
+pub fn parse_budget(text: &str) -> Result<Budget, String> {
+    let mut budget = Budget::default();
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        let n: u64 = words
+            .next()
+            .ok_or(format!("{} needs a value", word))?
+            .parse()
+            .map_err(|_| format!("Bad value for {}", word))?;
+        match word {
+            "playouts" => budget.playouts = Some(n as u32),
+            "time" => budget.time = Some(Duration::from_millis(n)),
+            _ => Err(format!("Unknown budget {}", word))?,
+        }
+    }
+    Ok(budget)
+}
+
+pub struct Protocol<G: Codec + 'static, W: Write + Send + 'static> {
+    state: G,
+    engine: Arc<Engine<G>>,
+    out: Arc<Mutex<W>>,
+    search: Option<(Arc<AtomicBool>, thread::JoinHandle<()>)>,
+}
+
+impl<G: Codec + 'static, W: Write + Send + 'static> Protocol<G, W> {
+    pub fn new(out: W) -> Self {
+        Protocol {
+            state: G::new(),
+            engine: Arc::new(Engine::new(G::new(), EngineSettings::default())),
+            out: Arc::new(Mutex::new(out)),
+            search: None,
+        }
+    }
+
+    // Answers commands until told to quit or the input ends
+    pub fn run<R: BufRead>(&mut self, input: R) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.command(&line?)? {
+                break;
+            }
+        }
+        self.stop();
+        Ok(())
+    }
+
+    // False once it's time to quit
+    pub fn command(&mut self, line: &str) -> io::Result<bool> {
+        let line = line.trim();
+        let (word, rest) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        if word.is_empty() {
+            return Ok(true);
+        }
+        if !(word == "show" || word == "legal" || word == "isready") {
+            self.stop();
+        }
+        let reply = match word {
+            "position" => self.position(rest),
+            "move" => self.play(rest),
+            "dice" => self.state.set_dice(rest).map(|_| Vec::new()),
+            "go" => parse_budget(rest).and_then(|budget| self.go(budget)),
+            "stop" => Ok(Vec::new()),
+            "show" => Ok(vec![format!("position {}", self.state.format_position())]),
+            "legal" => Ok(self.state
+                .possible_moves()
+                .iter()
+                .map(|m| self.state.format_move(m))
+                .collect()),
+            "isready" => Ok(vec!["readyok".to_string()]),
+            "quit" => return Ok(false),
+            _ => Err(format!("Unknown command {}", word)),
+        };
+        let lines = reply.unwrap_or_else(|e| vec![format!("error {}", e)]);
+        let mut out = self.out.lock().unwrap();
+        for line in lines {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()?;
+        Ok(true)
+    }
+
+    fn position(&mut self, text: &str) -> Result<Vec<String>, String> {
+        self.state = if text == "start" {
+            G::new()
+        } else {
+            G::parse_position(text)?
+        };
+        Ok(Vec::new())
+    }
+
+    fn play(&mut self, text: &str) -> Result<Vec<String>, String> {
+        if self.state.finished() {
+            Err("The game is over")?;
+        }
+        let m = self.state.parse_move(text)?;
+        self.state.apply(m);
+        Ok(Vec::new())
+    }
+
+    fn go(&mut self, budget: Budget) -> Result<Vec<String>, String> {
+        if self.state.finished() {
+            Err("The game is over")?;
+        }
+        // The engine follows moves and dice from one search to the next, keeping what it learnt
+        self.engine.ponder(self.state.clone());
+        let stop = Arc::new(AtomicBool::new(false));
+        let search = Search {
+            state: self.state.clone(),
+            engine: self.engine.clone(),
+            out: self.out.clone(),
+            stop: stop.clone(),
+        };
+        self.search = Some((stop, thread::spawn(move || search.run(budget))));
+        Ok(Vec::new())
+    }
+
+    // Waits for the search to report its best move
+    fn stop(&mut self) {
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::SeqCst);
+            handle.join().expect("Search thread panicked");
+        }
+    }
+}
+
+// Protocols dropped without being told to quit still end their search
+impl<G: Codec + 'static, W: Write + Send + 'static> Drop for Protocol<G, W> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+struct Search<G: Codec + 'static, W: Write + Send> {
+    state: G,
+    engine: Arc<Engine<G>>,
+    out: Arc<Mutex<W>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl<G: Codec + 'static, W: Write + Send> Search<G, W> {
+    fn run(&self, budget: Budget) {
+        let start = Instant::now();
+        let mut last_info = start;
+        let best = self.engine.best_move_until(budget, |_| {
+            if last_info.elapsed() >= Duration::from_millis(INFO_INTERVAL) {
+                self.say(&self.info(start));
+                last_info = Instant::now();
+            }
+            self.stop.load(Ordering::SeqCst)
+        });
+        self.engine.stop();
+        self.say(&self.info(start));
+        self.say(&format!(
+            "bestmove {}",
+            best.map_or("none".to_string(), |m| self.state.format_move(&m))
+        ));
+    }
+
+    fn info(&self, start: Instant) -> String {
+        let table = self.engine.table();
+        let (playouts, score) = table.nodes.get(&self.state).map_or((0, 0.0), |meta| {
+            let score = meta.scoreboard[&self.state.current_player()] / meta.playouts.max(1) as f64;
+            (meta.playouts, score)
+        });
+        let elapsed = start.elapsed();
+        let ms = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+        format!(
+            "info playouts {} nodes {} time {} score {:.3} pv {}",
+            playouts,
+            table.nodes.len(),
+            ms,
+            score,
+            pv(&table, &self.state).join(" ")
+        )
+    }
+
+    // Output is best effort, the protocol notices a closed output when it next answers
+    fn say(&self, line: &str) {
+        let mut out = self.out.lock().unwrap();
+        let _ = writeln!(out, "{}", line).and_then(|_| out.flush());
+    }
+}
+
+// The most visited line of play
+fn pv<G: Codec>(table: &MctsTable<G>, s: &G) -> Vec<String> {
+    let mut moves = Vec::new();
+    let mut s = s.clone();
+    while moves.len() < PV_LENGTH && table.nodes.get(&s).is_some_and(|meta| meta.playouts > 0) {
+        let m = match table.best_choice(&s) {
+            Some(m) => m,
+            None => break,
+        };
+        moves.push(s.format_move(&m));
+        s.apply(m);
+    }
+    moves
+}
@@ -0,0 +1,109 @@
+// Games saved part way through, to be resumed with the same players and engine settings.
+// Saved games are lines of text: the command line the game was started with,
+// the moves so far in the game's notation and what the engine learnt about the positions ahead
+//
+//     game-trees saved game
+//     arg backgammon
+//     arg --cube
+//     move 31
+//     move 8/5 6/5
+//     node 1200 1200 640 560 0:1:96 3:2:310
+//
+// See `MctsTable::write_nodes` for the nodes
+use game::Notation;
+use mcts_hashtable::MctsTable;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const HEADER: &'static str = "game-trees saved game";
+
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct SavedGame {
+    pub args: Vec<String>,
+    pub moves: Vec<String>,
+    pub nodes: Vec<String>,
+}
+
+impl SavedGame {
+    // The table, if any, should be rooted at the position the moves lead to
+    pub fn new<G: Notation>(args: &[String], start: &G, moves: &[G::Move], table: Option<&MctsTable<G>>) -> Self {
+        let mut s = start.clone();
+        let mut texts = Vec::new();
+        for m in moves {
+            texts.push(s.format_move(m));
+            s.apply(m.clone());
+        }
+        SavedGame {
+            args: args.to_vec(),
+            moves: texts,
+            nodes: table.map_or(Vec::new(), |table| table.write_nodes(&s)),
+        }
+    }
+
+    // Plays the moves from the start, which must be where the game started
+    pub fn replay<G: Notation>(&self, start: &G) -> Result<(Vec<G::Move>, G), String> {
+        let mut s = start.clone();
+        let mut moves = Vec::new();
+        for (i, text) in self.moves.iter().enumerate() {
+            let m = s.parse_move(text).map_err(|e| format!("Move {}: {}", i + 1, e))?;
+            moves.push(m.clone());
+            s.apply(m);
+        }
+        Ok((moves, s))
+    }
+
+    // Fills the table with what the engine had learnt, `s` being the position after the moves
+    pub fn restore<G: Notation>(&self, table: &mut MctsTable<G>, s: &G) -> Result<(), String> {
+        if self.nodes.is_empty() {
+            return Ok(());
+        }
+        table.read_nodes(s, &self.nodes)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next().map(|l| l.trim()) != Some(HEADER) {
+            Err("Not a saved game")?;
+        }
+        let mut saved = SavedGame::default();
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let (key, value) = match line.find(' ') {
+                Some(i) => (&line[..i], &line[(i + 1)..]),
+                None => (line, ""),
+            };
+            match key {
+                "arg" => saved.args.push(value.to_string()),
+                "move" => saved.moves.push(value.to_string()),
+                "node" => saved.nodes.push(value.to_string()),
+                _ => Err(format!("Unexpected line {}", line))?,
+            }
+        }
+        if saved.args.is_empty() {
+            Err("Which game was saved?")?;
+        }
+        Ok(saved)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+        Self::parse(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut f = io::BufWriter::new(File::create(path)?);
+        writeln!(f, "{}", HEADER)?;
+        for arg in &self.args {
+            writeln!(f, "arg {}", arg)?;
+        }
+        for m in &self.moves {
+            writeln!(f, "move {}", m)?;
+        }
+        for node in &self.nodes {
+            writeln!(f, "node {}", node)?;
+        }
+        f.flush()
+    }
+}
@@ -0,0 +1,119 @@
+// Exact values of games small enough to search to the end, to check the engine against.
+// Games must be without chance, with two players whose scores add up to nothing.
+// Searching is alpha-beta with a table of the bounds found on each state
+extern crate fnv;
+
+use game::{GameState, Score};
+use self::fnv::FnvHashMap;
+use std::f64;
+
+pub struct Solver<G: GameState> {
+    // Lower and upper bounds on the score of the player to move
+    bounds: FnvHashMap<G, (Score, Score)>,
+    // The lowest and highest scores games end with
+    range: (Score, Score),
+}
+
+impl<G: GameState> Default for Solver<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: GameState> Solver<G> {
+    pub fn new() -> Self {
+        Self::with_range(f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    // Knowing the scores games end with, such as -1 for a loss and 1 for a win,
+    // search stops as soon as a move is found to be as good as any can be
+    pub fn with_range(lowest: Score, highest: Score) -> Self {
+        Solver {
+            bounds: FnvHashMap::default(),
+            range: (lowest, highest),
+        }
+    }
+
+    // The score of the player to move when both play perfectly
+    pub fn value(&mut self, s: &G) -> Score {
+        let (lowest, highest) = self.range;
+        self.search(s, lowest, highest)
+    }
+
+    // The moves keeping the value
+    pub fn best_moves(&mut self, s: &G) -> Vec<G::Move> {
+        let value = self.value(s);
+        let mover = s.current_player();
+        s.possible_moves()
+            .into_iter()
+            .filter(|m| {
+                let mut new = s.clone();
+                new.apply(m.clone());
+                self.value_for(&new, &mover) == value
+            })
+            .collect()
+    }
+
+    // States searched so far
+    pub fn states(&self) -> usize {
+        self.bounds.len()
+    }
+
+    fn value_for(&mut self, s: &G, p: &G::Player) -> Score {
+        let value = self.value(s);
+        if s.current_player() == *p { value } else { -value }
+    }
+
+    fn search(&mut self, s: &G, mut alpha: Score, mut beta: Score) -> Score {
+        let mover = s.current_player();
+        if let Some(scores) = s.scores() {
+            return scores[&mover];
+        }
+        let (lower, upper) = self.bounds
+            .get(s)
+            .cloned()
+            .unwrap_or((f64::NEG_INFINITY, f64::INFINITY));
+        if lower >= beta || lower == upper {
+            return lower;
+        }
+        if upper <= alpha {
+            return upper;
+        }
+        alpha = alpha.max(lower);
+        beta = beta.min(upper);
+        let window = (alpha, beta);
+        let mut best = f64::NEG_INFINITY;
+        let mut children: Vec<G> = s.legal_moves()
+            .into_iter()
+            .map(|m| {
+                let mut new = s.clone();
+                new.apply(m);
+                new
+            })
+            .collect();
+        // Moves ending the game are quickest to judge
+        children.sort_by_key(|new| !new.finished());
+        for new in children {
+            // Some games have players moving twice in a row
+            let score = if new.current_player() == mover {
+                self.search(&new, alpha, beta)
+            } else {
+                -self.search(&new, -beta, -alpha)
+            };
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        let bounds = if best <= window.0 {
+            (lower, best)
+        } else if best >= window.1 {
+            (best, upper)
+        } else {
+            (best, best)
+        };
+        self.bounds.insert(s.clone(), bounds);
+        best
+    }
+}
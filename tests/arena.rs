@@ -0,0 +1,35 @@
+extern crate game_trees;
+
+use game_trees::arena::{Arena, EngineConfig, Format, Sprt, Uniform, Verdict};
+use game_trees::game::nim::Nim;
+use std::sync::Arc;
+
+fn arena(threads: usize) -> Arena<Nim> {
+    let engines = vec![EngineConfig::new("strong", 300), EngineConfig::new("weak", 10)];
+    let mut arena = Arena::new((false, true), Arc::new(Uniform), engines);
+    arena.settings.format = Format::Gauntlet;
+    arena.settings.games = 20;
+    arena.settings.threads = threads;
+    arena
+}
+
+#[test]
+fn results_do_not_depend_on_threads() {
+    let one = arena(1).run(|_| {});
+    let two = arena(2).run(|_| {});
+    assert_eq!(one, two);
+    let p = &one.pairings[0];
+    assert_eq!(p.games, 20);
+    assert!(p.score() > 0.5, "strong scored {}", p.score());
+}
+
+#[test]
+fn sprt_stops_decided_pairings() {
+    let mut arena = arena(1);
+    arena.settings.games = 1000;
+    arena.settings.sprt = Some(Sprt::new(0.0, 50.0));
+    let report = arena.run(|_| {});
+    let p = &report.pairings[0];
+    assert_eq!(p.verdict, Some(Verdict::H1));
+    assert!(p.games < 1000);
+}
@@ -0,0 +1,72 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Move, Point};
+use game_trees::game::backgammon::Location::Board;
+use game_trees::game::backgammon::analysis::Class;
+use game_trees::game::backgammon::notation::parse_move;
+
+#[test]
+fn both_sides_start_with_167_pips() {
+    let s = Backgammon::new();
+    assert_eq!(s.pip_counts(), (167, 167));
+    assert!(s.is_contact());
+    assert!(!s.is_race());
+    assert_eq!(s.classify(), Class::Contact);
+}
+
+#[test]
+fn running_past_the_last_piece_breaks_contact() {
+    // White's last piece on black's five point, behind black's pieces on its six point
+    let mut s = Backgammon::from_counts(&[(Board(Point(5)), 1, 0), (Board(Point(20)), 14, 0), (Board(Point(6)), 0, 15)], true);
+    assert!(s.is_contact());
+    assert_eq!(s.pip_counts(), (20 + 14 * 5, 15 * 6));
+    s.apply(Move::Roll((2, 1)));
+    let m = parse_move(&s, "20/17").unwrap();
+    s.apply(m);
+    assert!(!s.is_contact());
+    assert!(s.is_race());
+    assert_eq!(s.classify(), Class::Race);
+    assert_eq!(s.pip_count(true), 17 + 14 * 5);
+}
+
+#[test]
+fn positions_are_classified() {
+    let class = |counts: &[_]| Backgammon::from_counts(counts, true).classify();
+    // White has borne off every piece
+    assert_eq!(class(&[(Board(Point(6)), 0, 15)]), Class::Over);
+    assert_eq!(class(&[(Board(Point(20)), 15, 0), (Board(Point(5)), 0, 15)]), Class::Bearoff);
+    assert_eq!(class(&[(Board(Point(13)), 15, 0), (Board(Point(10)), 0, 15)]), Class::Race);
+    assert_eq!(Backgammon::new().classify(), Class::Contact);
+    // White holds black's five point
+    assert_eq!(
+        class(&[(Board(Point(5)), 2, 0), (Board(Point(20)), 13, 0), (Board(Point(6)), 0, 13), (Board(Point(13)), 0, 2)]),
+        Class::Holding
+    );
+    // Black's four to eight points trap white's back pieces
+    assert_eq!(
+        class(&[
+            (Board(Point(1)), 2, 0),
+            (Board(Point(20)), 13, 0),
+            (Board(Point(4)), 0, 2),
+            (Board(Point(5)), 0, 2),
+            (Board(Point(6)), 0, 2),
+            (Board(Point(7)), 0, 2),
+            (Board(Point(8)), 0, 2),
+            (Board(Point(13)), 0, 5),
+        ]),
+        Class::Prime
+    );
+    // White is far behind in the race with anchors on black's one and three points
+    assert_eq!(
+        class(&[
+            (Board(Point(1)), 2, 0),
+            (Board(Point(3)), 2, 0),
+            (Board(Point(12)), 11, 0),
+            (Board(Point(4)), 0, 5),
+            (Board(Point(5)), 0, 5),
+            (Board(Point(6)), 0, 5),
+        ]),
+        Class::BackGame
+    );
+}
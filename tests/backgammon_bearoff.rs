@@ -0,0 +1,58 @@
+extern crate game_trees;
+
+use game_trees::game::backgammon::{Backgammon, Point};
+use game_trees::game::backgammon::Location::Board;
+use game_trees::game::backgammon::bearoff::{positions, rank, OneSided, TwoSided};
+
+#[test]
+fn positions_and_ranks_agree() {
+    for checkers in 0..5 {
+        let all = positions(checkers);
+        // Up to `checkers` pieces on six points: (checkers + 6) choose 6
+        let expected = (1..7).fold(1, |c, k| c * (checkers as usize + k) / k);
+        assert_eq!(all.len(), expected);
+        for (i, &home) in all.iter().enumerate() {
+            assert_eq!(rank(home, checkers), i, "{:?}", home);
+        }
+    }
+}
+
+#[test]
+fn one_sided_rolls_are_exact() {
+    let db = OneSided::generate(3);
+    assert_eq!(db.expected_rolls([0; 6]), Some(0.0));
+    assert_eq!(db.expected_rolls([1, 0, 0, 0, 0, 0]), Some(1.0));
+    assert_eq!(db.expected_rolls([2, 0, 0, 0, 0, 0]), Some(1.0));
+    // Only 21, 31, 41, 32 and 11 leave a piece on the six point short, a quarter of the time
+    assert_eq!(db.expected_rolls([0, 0, 0, 0, 0, 1]), Some(1.25));
+    assert_eq!(db.expected_rolls([4, 0, 0, 0, 0, 0]), None);
+}
+
+#[test]
+fn two_sided_chances_are_exact() {
+    let db = TwoSided::generate(2);
+    assert_eq!(db.win_chance([1, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0]), Some(1.0));
+    // Saved to 16 bits
+    let chance = db.win_chance([0, 0, 0, 0, 0, 1], [1, 0, 0, 0, 0, 0]).unwrap();
+    assert!((chance - 0.75).abs() < 1e-4);
+    assert_eq!(db.win_chance([0; 6], [1, 0, 0, 0, 0, 0]), None);
+    assert_eq!(db.win_chance([3, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0]), None);
+}
+
+#[test]
+fn databases_are_saved_and_read_back() {
+    let db = OneSided::generate(4);
+    let path = std::env::temp_dir().join(format!("game-trees-bearoff-test-{}.db", std::process::id()));
+    db.save(&path).unwrap();
+    let read = OneSided::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read, db);
+}
+
+#[test]
+fn effective_pip_counts_use_the_database() {
+    // White's last piece on its one point, black's on its six point
+    let s = Backgammon::from_counts(&[(Board(Point(24)), 1, 0), (Board(Point(6)), 0, 1)], true);
+    assert_eq!(s.effective_pip_count(true), 49.0 / 6.0);
+    assert!((s.effective_pip_count(false) - 1.25 * 49.0 / 6.0).abs() < 1e-9);
+}
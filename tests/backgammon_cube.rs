@@ -0,0 +1,146 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Cube, CubeAction, Move, Point, MAX_CUBE};
+use game_trees::game::backgammon::Location::Board;
+
+const DOUBLE: Move = Move::Cube(CubeAction::Double);
+const NO_DOUBLE: Move = Move::Cube(CubeAction::NoDouble);
+const TAKE: Move = Move::Cube(CubeAction::Take);
+const DROP: Move = Move::Cube(CubeAction::Drop);
+
+// Rolls and plays the first legal move
+fn play(s: &mut Backgammon, roll: (u8, u8)) {
+    s.apply(Move::Roll(roll));
+    let m = s.legal_moves()[0].clone();
+    s.apply(m);
+}
+
+// Black opens with 31, leaving white to decide on the cube
+fn opened() -> Backgammon {
+    let mut s = Backgammon::with_cube();
+    s.player = false;
+    play(&mut s, (3, 1));
+    s
+}
+
+fn points(s: &Backgammon, white: bool) -> f64 {
+    s.scores().expect("The game is over")[&Some(white)]
+}
+
+#[test]
+fn the_player_on_roll_may_double_before_rolling() {
+    let s = opened();
+    assert_eq!(s.current_player(), Some(true));
+    assert_eq!(s.legal_moves(), vec![NO_DOUBLE, DOUBLE]);
+    let mut rolling = s.clone();
+    rolling.apply(NO_DOUBLE);
+    assert_eq!(rolling.current_player(), None);
+    assert_eq!(rolling.legal_moves().len(), 21);
+    // The opponent answers a double
+    let mut doubled = s.clone();
+    doubled.apply(DOUBLE);
+    assert_eq!(doubled.current_player(), Some(false));
+    assert_eq!(doubled.legal_moves(), vec![TAKE, DROP]);
+}
+
+#[test]
+fn only_the_owner_may_redouble_after_a_take() {
+    let mut s = opened();
+    s.apply(DOUBLE);
+    s.apply(TAKE);
+    assert_eq!(
+        s.cube,
+        Some(Cube {
+            value: 2,
+            owner: Some(false),
+        })
+    );
+    // White doubled, so rolls straight away
+    assert_eq!(s.current_player(), None);
+    play(&mut s, (6, 5));
+    assert!(s.may_double());
+    assert_eq!(s.legal_moves(), vec![NO_DOUBLE, DOUBLE]);
+    s.apply(NO_DOUBLE);
+    play(&mut s, (4, 2));
+    // White no longer has the cube
+    assert!(!s.may_double());
+    assert_eq!(s.current_player(), None);
+    // Black's redouble is taken and the cube goes back to white at 4
+    play(&mut s, (5, 2));
+    s.apply(DOUBLE);
+    s.apply(TAKE);
+    assert_eq!(
+        s.cube,
+        Some(Cube {
+            value: 4,
+            owner: Some(true),
+        })
+    );
+}
+
+#[test]
+fn the_cube_stops_at_64() {
+    let mut s = Backgammon::with_cube();
+    s.cube = Some(Cube {
+        value: 32,
+        owner: Some(true),
+    });
+    s.player = false;
+    play(&mut s, (3, 1));
+    assert_eq!(s.legal_moves(), vec![NO_DOUBLE, DOUBLE]);
+    s.apply(DOUBLE);
+    s.apply(TAKE);
+    assert_eq!(s.cube.map(|c| c.value), Some(MAX_CUBE));
+    play(&mut s, (6, 5));
+    // Black owns the cube, but may not turn it again
+    assert!(!s.may_double());
+    assert_eq!(s.current_player(), None);
+}
+
+#[test]
+fn dropping_concedes_the_value_before_the_double() {
+    let mut s = opened();
+    s.apply(DOUBLE);
+    s.apply(DROP);
+    assert!(s.finished());
+    assert_eq!((points(&s, true), points(&s, false)), (1.0, -1.0));
+    let mut s = opened();
+    s.apply(DOUBLE);
+    s.apply(TAKE);
+    play(&mut s, (6, 5));
+    s.apply(DOUBLE);
+    s.apply(DROP);
+    assert_eq!((points(&s, true), points(&s, false)), (-2.0, 2.0));
+}
+
+#[test]
+fn scores_are_multiplied_by_the_cube() {
+    // White bears off its last piece, black having borne off `black_home` pieces
+    let finish = |value: u32, black_home: u8| {
+        let mut s = Backgammon::from_counts(&[(Board(Point(24)), 1, 0), (Board(Point(6)), 0, 15 - black_home)], true);
+        s.cube = Some(Cube {
+            value: value,
+            owner: Some(false),
+        });
+        play(&mut s, (2, 1));
+        assert!(s.finished());
+        points(&s, true)
+    };
+    assert_eq!(finish(1, 1), 1.0);
+    assert_eq!(finish(4, 1), 4.0);
+    // Gammons
+    assert_eq!(finish(1, 0), 2.0);
+    assert_eq!(finish(4, 0), 8.0);
+}
+
+#[test]
+fn cubeless_games_never_double() {
+    let mut s = Backgammon::new();
+    s.player = false;
+    for &roll in &[(3, 1), (6, 5), (4, 2), (5, 2)] {
+        assert!(!s.legal_moves().contains(&DOUBLE));
+        play(&mut s, roll);
+        assert_eq!(s.current_player(), None);
+    }
+}
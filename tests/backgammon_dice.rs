@@ -0,0 +1,58 @@
+extern crate game_trees;
+
+use game_trees::game::backgammon::{Backgammon, Move};
+use game_trees::game::backgammon::dice::{Dice, RandomDice, ReplayDice, Roller};
+use game_trees::play::{Choice, Controller, Kind};
+use std::io;
+
+fn rolls<D: Dice>(dice: &mut D, n: usize) -> Vec<(u8, u8)> {
+    (0..n).map(|_| dice.roll().unwrap()).collect()
+}
+
+#[test]
+fn replayed_rolls_are_read_in_order() {
+    let text = "# Opening rolls\n65 3-1\n  # 42 is skipped\n\n12 6-6\n";
+    let mut dice = ReplayDice::parse(text).unwrap();
+    assert_eq!(dice, ReplayDice::new(vec![(6, 5), (3, 1), (2, 1), (6, 6)]));
+    assert_eq!(rolls(&mut dice, 4), vec![(6, 5), (3, 1), (2, 1), (6, 6)]);
+    let error = dice.roll().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    assert!(ReplayDice::parse("65 67").is_err());
+    assert!(ReplayDice::parse("654").is_err());
+}
+
+#[test]
+fn replayed_rolls_are_loaded_from_files() {
+    let path = std::env::temp_dir().join(format!("game-trees-dice-test-{}.txt", std::process::id()));
+    std::fs::write(&path, "# Rolls\n5-2 44\n").unwrap();
+    let mut dice = ReplayDice::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(rolls(&mut dice, 2), vec![(5, 2), (4, 4)]);
+}
+
+#[test]
+fn random_rolls_depend_only_on_the_seed() {
+    let first = rolls(&mut RandomDice::new(7), 100);
+    assert_eq!(rolls(&mut RandomDice::new(7), 100), first);
+    assert!(rolls(&mut RandomDice::new(8), 100) != first);
+    assert!(first.iter().all(|&(x, y)| x >= y && y >= 1 && x <= 6));
+    // Every roll turns up eventually
+    let many = rolls(&mut RandomDice::new(7), 2000);
+    for x in 1..7 {
+        for y in 1..(x + 1) {
+            assert!(many.contains(&(x, y)), "{}{}", x, y);
+        }
+    }
+}
+
+#[test]
+fn rollers_choose_rolls() {
+    let mut roller = Roller(Box::new(ReplayDice::new(vec![(4, 2)])));
+    assert_eq!(roller.kind(), Kind::Chance);
+    let s = Backgammon::with_cube();
+    match roller.choose(&s).unwrap() {
+        Choice::Move(m) => assert_eq!(m, Move::Roll((4, 2))),
+        _ => panic!("Expected a roll"),
+    }
+    assert!(roller.choose(&s).is_err());
+}
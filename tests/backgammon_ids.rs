@@ -0,0 +1,100 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Cube, CubeAction, Move};
+use game_trees::game::backgammon::matchplay::{BackgammonMatch, Crawford};
+
+// The opening 31 played as 8/5 6/5, then the opponent to move
+fn after_opening(s: &Backgammon) -> Backgammon {
+    let mut s = s.clone();
+    s.apply(Move::Roll((3, 1)));
+    let m = s.legal_moves()[0].clone();
+    s.apply(m);
+    s
+}
+
+fn assert_round_trip(s: &Backgammon, name: &str) {
+    assert_eq!(&Backgammon::from_gnubg_id(&s.gnubg_id()).unwrap(), s, "{}", name);
+}
+
+#[test]
+fn gnubg_ids_keep_the_whole_state() {
+    let cubeless = Backgammon::new();
+    let cube = Backgammon::with_cube();
+    let mut rolled = cube.clone();
+    rolled.apply(Move::Roll((6, 5)));
+    let deciding = after_opening(&cube);
+    let mut declined = deciding.clone();
+    declined.apply(Move::Cube(CubeAction::NoDouble));
+    let mut doubled = deciding.clone();
+    doubled.apply(Move::Cube(CubeAction::Double));
+    let mut taken = doubled.clone();
+    taken.apply(Move::Cube(CubeAction::Take));
+    let mut dropped = doubled.clone();
+    dropped.apply(Move::Cube(CubeAction::Drop));
+    let cases = [
+        (cubeless.clone(), "cubeless start"),
+        (after_opening(&cubeless), "cubeless after a move"),
+        (cube, "start with a cube"),
+        (rolled, "dice to play"),
+        (deciding, "cube decision"),
+        (declined, "no double"),
+        (doubled, "take decision"),
+        (taken, "cube owned after a take"),
+        (dropped, "dropped"),
+    ];
+    for &(ref s, name) in &cases {
+        assert_round_trip(s, name);
+    }
+}
+
+#[test]
+fn match_ids_keep_the_score_and_crawford_game() {
+    let mut m = BackgammonMatch::with_length(5);
+    m.score = (4, 2);
+    m.crawford = Crawford::Game;
+    m.game = Backgammon::new();
+    m.game.apply(Move::Roll((4, 2)));
+    let read = BackgammonMatch::from_gnubg_id(&m.gnubg_id()).unwrap();
+    assert_eq!(read, m);
+    assert_eq!(BackgammonMatch::from_xgid(&m.xgid()).unwrap(), m);
+    // After the Crawford game the cube is back
+    m.crawford = Crawford::After;
+    m.score = (4, 3);
+    m.game = Backgammon::with_cube();
+    assert_eq!(BackgammonMatch::from_gnubg_id(&m.gnubg_id()).unwrap(), m);
+}
+
+#[test]
+fn known_ids() {
+    assert_eq!(Backgammon::new().position_id(), "4HPwATDgc/ABMA");
+    // The example in the GNU Backgammon manual: a 9 point match at 2-4, the cube on 2 owned by
+    // player 0 and player 1 to play 52
+    let m = BackgammonMatch::from_gnubg_id("4HPwATDgc/ABMA:QYkqASAAIAAA").unwrap();
+    assert_eq!((m.length, m.score, m.crawford), (9, (2, 4), Crawford::Before));
+    assert_eq!(
+        m.game.cube,
+        Some(Cube {
+            value: 2,
+            owner: Some(true),
+        })
+    );
+    assert_eq!((m.game.player, m.game.dice), (false, (5, 2)));
+    // The starting position with X, black here, to roll in a money game
+    let xgid = "XGID=-b----E-C---eE---c-e----B-:0:0:1:00:0:0:0:0:10";
+    let mut s = Backgammon::with_cube();
+    s.player = false;
+    assert_eq!(s.xgid(), xgid);
+    let read = Backgammon::from_xgid(xgid).unwrap();
+    assert_eq!((&read.counts, read.player, read.cube), (&s.counts, s.player, s.cube));
+}
+
+#[test]
+fn xgids_keep_the_cube() {
+    let cubeless = after_opening(&Backgammon::new());
+    assert_eq!(Backgammon::from_xgid(&cubeless.xgid()).unwrap(), cubeless);
+    let mut doubled = after_opening(&Backgammon::with_cube());
+    assert_eq!(Backgammon::from_xgid(&doubled.xgid()).unwrap(), doubled);
+    doubled.apply(Move::Cube(CubeAction::Double));
+    assert_eq!(Backgammon::from_xgid(&doubled.xgid()).unwrap(), doubled);
+}
@@ -0,0 +1,110 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Move, Point};
+use game_trees::game::backgammon::Location::Board;
+use game_trees::game::backgammon::matchplay::{BackgammonMatch, Crawford};
+use game_trees::game::backgammon::met::MatchEquityTable;
+
+// Ends the current game with a single win, the winner bearing off its last piece
+fn win(m: &BackgammonMatch, white: bool) -> BackgammonMatch {
+    let mut m = m.clone();
+    // The winner's last piece and the loser's other pieces, each on its own one point
+    let counts = if white {
+        [(Board(Point(24)), 1, 0), (Board(Point(1)), 0, 14)]
+    } else {
+        [(Board(Point(1)), 0, 1), (Board(Point(24)), 14, 0)]
+    };
+    let mut game = Backgammon::from_counts(&counts, white);
+    game.cube = m.game.cube;
+    game.apply(Move::Roll((2, 1)));
+    let bear_off = game.legal_moves()[0].clone();
+    game.apply(bear_off);
+    assert!(game.finished());
+    m.game = game;
+    m
+}
+
+#[test]
+fn the_crawford_game_follows_the_first_player_getting_one_away() {
+    let start = BackgammonMatch::with_length(3);
+    assert_eq!(start.crawford, Crawford::Before);
+    let m = win(&start, true).next_game().unwrap();
+    assert_eq!((m.score, m.crawford), ((1, 0), Crawford::Before));
+    let m = win(&m, true).next_game().unwrap();
+    assert_eq!((m.score, m.crawford), ((2, 0), Crawford::Game));
+    assert_eq!(m.game.cube, None);
+    let m = win(&m, false).next_game().unwrap();
+    assert_eq!((m.score, m.crawford), ((2, 1), Crawford::After));
+    assert!(m.game.cube.is_some());
+    // Only one game is ever played without the cube
+    let m = win(&m, false).next_game().unwrap();
+    assert_eq!((m.score, m.crawford), ((2, 2), Crawford::After));
+    assert!(m.game.cube.is_some());
+}
+
+#[test]
+fn the_match_ends_with_the_last_point() {
+    let mut m = BackgammonMatch::with_length(3);
+    m.score = (1, 2);
+    m.crawford = Crawford::After;
+    assert_eq!(m.winner(), None);
+    assert!(m.next_game().is_none(), "The game isn't over");
+    let lost = win(&m, false);
+    assert_eq!(lost.winner(), Some(false));
+    assert!(lost.next_game().is_none());
+    assert_eq!(lost.scores().unwrap()[&Some(false)], 1.0);
+    // Winning the game only brings white closer
+    let won = win(&m, true);
+    assert_eq!(won.winner(), None);
+    let table = MatchEquityTable::generated(3);
+    assert_eq!(won.scores().unwrap()[&Some(true)], table.win_chance(1, 1, true));
+}
+
+#[test]
+fn match_equity_is_the_same_from_both_sides() {
+    let table = MatchEquityTable::generated(11);
+    for a in 1..12 {
+        for b in 1..12 {
+            let total = table.win_chance(a, b, false) + table.win_chance(b, a, false);
+            assert!((total - 1.0).abs() < 1e-9, "{}-away against {}-away", a, b);
+        }
+        let total = table.win_chance(1, a, true) + table.win_chance(a, 1, true);
+        assert!((total - 1.0).abs() < 1e-9, "{}-away after the Crawford game", a);
+    }
+    // Needing fewer points is better
+    for a in 1..11 {
+        assert!(table.win_chance(a, 5, false) > table.win_chance(a + 1, 5, false));
+    }
+    assert_eq!(table.win_chance(0, 3, false), 1.0);
+    assert_eq!(table.win_chance(3, 0, false), 0.0);
+}
+
+#[test]
+fn match_equity_tables_are_saved_and_read_back() {
+    let table = MatchEquityTable::generated(7);
+    let path = std::env::temp_dir().join(format!("game-trees-met-test-{}.txt", std::process::id()));
+    table.save(&path).unwrap();
+    let read = MatchEquityTable::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.size(), 7);
+    for a in 1..8 {
+        for b in 1..8 {
+            for &post in &[false, true] {
+                // Saved to four decimals
+                let difference = read.win_chance(a, b, post) - table.win_chance(a, b, post);
+                assert!(difference.abs() <= 5e-5, "{}-away against {}-away", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn malformed_tables_are_rejected() {
+    assert!(MatchEquityTable::parse("0.5 0.3\n0.7 0.5\n").is_err(), "Missing post-Crawford row");
+    assert!(MatchEquityTable::parse("0.5 0.3\n0.7\npost-crawford\n0.5 0.3\n").is_err(), "Not square");
+    assert!(MatchEquityTable::parse("0.5 x\n0.7 0.5\npost-crawford\n0.5 0.3\n").is_err(), "Not a number");
+    let table = MatchEquityTable::parse("# 2 points\n0.5 0.3\n0.7 0.5\npost-crawford\n0.5 0.3\n").unwrap();
+    assert_eq!(table.win_chance(2, 1, false), 0.7);
+    assert_eq!(table.win_chance(2, 1, true), 0.3);
+}
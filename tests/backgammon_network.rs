@@ -0,0 +1,104 @@
+extern crate game_trees;
+extern crate rand;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Move};
+use game_trees::game::backgammon::Location::Bar;
+use game_trees::game::backgammon::network::{inputs, Network, Outcomes, INPUTS};
+use rand::{SeedableRng, XorShiftRng};
+
+fn rng() -> XorShiftRng {
+    XorShiftRng::from_seed([1, 2, 3, 4])
+}
+
+// The four units of a point, counted from white's side of the board
+fn point_units(v: &[f64], white: bool, point: usize) -> &[f64] {
+    let start = if white { 0 } else { 98 } + (point - 1) * 4;
+    &v[start..(start + 4)]
+}
+
+#[test]
+fn positions_are_encoded_in_198_inputs() {
+    assert_eq!(INPUTS, 198);
+    let mut s = Backgammon::new();
+    s.player = true;
+    let v = inputs(&s);
+    assert_eq!(v.len(), INPUTS);
+    assert_eq!(point_units(&v, true, 1), &[1.0, 1.0, 0.0, 0.0]);
+    assert_eq!(point_units(&v, true, 12), &[1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(point_units(&v, true, 17), &[1.0, 1.0, 1.0, 0.0]);
+    assert_eq!(point_units(&v, false, 6), &[1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(point_units(&v, false, 2), &[0.0; 4]);
+    // Bar and borne off pieces, then whose turn it is
+    assert_eq!(&v[96..98], &[0.0, 0.0]);
+    assert_eq!(&v[194..198], &[0.0, 0.0, 1.0, 0.0]);
+    // Black hits white's blot on black's five point
+    s.apply(Move::Roll((4, 1)));
+    let m = s.legal_moves()[0].clone();
+    s.apply(m);
+    s.apply(Move::Roll((6, 5)));
+    let hit = s.legal_moves().into_iter().map(|m| {
+        let mut new = s.clone();
+        new.apply(m);
+        new
+    }).find(|new| new.pieces(Bar, true) > 0);
+    let v = inputs(&hit.expect("A hit is possible"));
+    assert_eq!(v[96], 0.5);
+    assert_eq!(&v[196..198], &[1.0, 0.0]);
+}
+
+#[test]
+fn networks_are_saved_and_read_back() {
+    let n = Network::new(3, &mut rng());
+    let path = std::env::temp_dir().join(format!("game-trees-network-test-{}.txt", std::process::id()));
+    n.save(&path).unwrap();
+    let read = Network::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read, n);
+    assert!(Network::parse("3\n1 2 3\n").is_err());
+    assert!(Network::parse("").is_err());
+}
+
+#[test]
+fn learning_moves_the_outputs_toward_the_target() {
+    let mut n = Network::new(5, &mut rng());
+    let s = Backgammon::new();
+    let target = Outcomes {
+        win: 1.0,
+        win_gammon: 1.0,
+        win_backgammon: 0.0,
+        lose_gammon: 0.0,
+        lose_backgammon: 0.0,
+    };
+    let error = |o: Outcomes| {
+        vec![
+            o.win - target.win,
+            o.win_gammon - target.win_gammon,
+            o.win_backgammon - target.win_backgammon,
+            o.lose_gammon - target.lose_gammon,
+            o.lose_backgammon - target.lose_backgammon,
+        ]
+    };
+    let mut before = error(n.outcomes(&s));
+    for _ in 0..10 {
+        n.learn(&s, target, 0.1);
+        let after = error(n.outcomes(&s));
+        for k in 0..before.len() {
+            assert!(after[k].abs() < before[k].abs(), "Output {}", k);
+        }
+        before = after;
+    }
+}
+
+#[test]
+fn training_depends_only_on_the_dice() {
+    let start = Network::new(5, &mut rng());
+    let train = || {
+        let mut n = start.clone();
+        n.train(2, 0.1, 0.7, &mut rng());
+        n
+    };
+    let trained = train();
+    assert!(trained != start);
+    assert_eq!(train(), trained);
+}
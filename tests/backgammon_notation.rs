@@ -0,0 +1,121 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, CubeAction, Move, Point, Roll};
+use game_trees::game::backgammon::Location::{self, Bar, Board, Home};
+use game_trees::game::backgammon::notation::{format_move, parse_move, parse_segments, resolve, Segment};
+
+// Location, white pieces, black pieces, the rest being home
+fn position(layout: &[(Location, u8, u8)], player: bool, roll: Roll) -> Backgammon {
+    let mut s = Backgammon::from_counts(layout, player);
+    s.apply(Move::Roll(roll));
+    s
+}
+
+fn opening(player: bool, roll: Roll) -> Backgammon {
+    let mut s = Backgammon::new();
+    s.player = player;
+    s.apply(Move::Roll(roll));
+    s
+}
+
+// The move read from the text, which must be written back as `formatted`
+fn assert_notation(s: &Backgammon, text: &str, formatted: &str) -> Move {
+    let m = parse_move(s, text).unwrap();
+    assert_eq!(format_move(s, &m), formatted, "{}", text);
+    m
+}
+
+fn after(s: &Backgammon, m: Move) -> Backgammon {
+    let mut s = s.clone();
+    s.apply(m);
+    s
+}
+
+#[test]
+fn moves_are_read_from_the_movers_side() {
+    let black = opening(false, (6, 2));
+    let m = assert_notation(&black, "24/18 13/11", "24/18 13/11");
+    let new = after(&black, m);
+    assert_eq!((new.pieces(Board(Point(18)), false), new.pieces(Board(Point(11)), false)), (1, 1));
+    // White counts its points from the other end
+    let white = opening(true, (6, 2));
+    let m = assert_notation(&white, "24/18 13/11", "24/18 13/11");
+    let new = after(&white, m);
+    assert_eq!((new.pieces(Board(Point(7)), true), new.pieces(Board(Point(14)), true)), (1, 1));
+    // The order of the pieces doesn't matter
+    assert_eq!(parse_move(&black, "13/11 24/18"), parse_move(&black, "24/18 13/11"));
+    assert!(parse_move(&black, "24/23 13/11").is_err());
+}
+
+#[test]
+fn pieces_moving_twice_are_joined() {
+    let s = opening(false, (6, 4));
+    let m = assert_notation(&s, "24/14", "24/18/14");
+    assert_eq!(parse_move(&s, "24/18/14"), Ok(m.clone()));
+    assert_eq!(parse_move(&s, "24/20 20/14"), Ok(m));
+}
+
+#[test]
+fn repeated_moves_are_counted() {
+    let s = opening(false, (4, 4));
+    let m = assert_notation(&s, "8/4 8/4 6/2 6/2", "8/4(2) 6/2(2)");
+    assert_eq!(parse_move(&s, "8/4(2) 6/2(2)"), Ok(m));
+}
+
+#[test]
+fn entering_from_the_bar_and_hitting() {
+    // White's 22 point is black's 3, 6 is black's 19
+    let layout = [(Bar, 1, 0), (Board(Point(19)), 14, 0), (Board(Point(3)), 0, 1), (Board(Point(12)), 0, 14)];
+    let s = position(&layout, true, (3, 1));
+    let m = assert_notation(&s, "bar/22* 6/5", "bar/22* 6/5");
+    assert_eq!(after(&s, m.clone()).pieces(Bar, false), 1);
+    // Unmarked hits are found all the same
+    assert_eq!(parse_move(&s, "25/22 6/5"), Ok(m));
+}
+
+#[test]
+fn bearing_off() {
+    let layout = [(Board(Point(6)), 0, 2), (Board(Point(5)), 0, 13), (Board(Point(20)), 15, 0)];
+    let s = position(&layout, false, (6, 5));
+    let m = assert_notation(&s, "6/off 5/off", "6/off 5/off");
+    assert_eq!(parse_move(&s, "6/0 5/0"), Ok(m.clone()));
+    assert_eq!(after(&s, m).pieces(Home, false), 2);
+}
+
+#[test]
+fn rolls_and_cube_actions() {
+    let mut s = Backgammon::with_cube();
+    s.player = false;
+    s.apply(Move::Roll((3, 1)));
+    let m = parse_move(&s, "8/5 6/5").unwrap();
+    s.apply(m);
+    assert_eq!(assert_notation(&s, "Double", "double"), Move::Cube(CubeAction::Double));
+    assert_eq!(assert_notation(&s, "roll", "no double"), Move::Cube(CubeAction::NoDouble));
+    let mut rolling = s.clone();
+    rolling.apply(Move::Cube(CubeAction::NoDouble));
+    assert_eq!(assert_notation(&rolling, "6-5", "65"), Move::Roll((6, 5)));
+    assert_eq!(parse_move(&rolling, "56"), Ok(Move::Roll((6, 5))));
+    assert!(parse_move(&rolling, "67").is_err());
+    // Only declining to double is called rolling
+    assert!(parse_move(&rolling, "roll").is_err());
+    s.apply(Move::Cube(CubeAction::Double));
+    assert_eq!(assert_notation(&s, "take", "take"), Move::Cube(CubeAction::Take));
+    assert_eq!(assert_notation(&s, "pass", "drop"), Move::Cube(CubeAction::Drop));
+}
+
+#[test]
+fn segments_resolve_to_legal_moves() {
+    let s = opening(false, (6, 5));
+    let segments = parse_segments("24/18/13", false).unwrap();
+    assert_eq!(
+        segments,
+        vec![
+            Segment { from: Board(Point(24)), to: Board(Point(18)), hit: false },
+            Segment { from: Board(Point(18)), to: Board(Point(13)), hit: false },
+        ]
+    );
+    let m = resolve(&s, &segments).unwrap();
+    assert_eq!(format_move(&s, &m), "24/18/13");
+    assert!(resolve(&s, &parse_segments("24/13 6/1", false).unwrap()).is_err());
+}
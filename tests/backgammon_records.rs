@@ -0,0 +1,127 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{CubeAction, Move};
+use game_trees::game::backgammon::records::MatchRecord;
+
+// Laid out as GNU Backgammon exports matches, with the result on lines of their own
+const MAT: &str = "
+ 3 point match
+
+ Game 1
+ Alice : 0                              Bob : 0
+  1)                                    31: 8/5 6/5
+  2) 52: 13/8 13/11                     Doubles => 2
+  3)  Takes                             64: 24/18 13/9
+  4)  Doubles => 4                      Drops
+      Wins 2 points
+
+ Game 2
+ Alice : 2                              Bob : 0
+  1) 42: 8/4 6/4                        63: 24/18 13/10
+  2) 66: 24/18 24/18 8/2 8/2            21: 13/11 6/5
+
+ Game 3
+ Alice : 2                              Bob : 1
+  1) 65: 24/18 18/13                    Doubles => 2
+  2)  Drops
+                                        Wins 1 point
+";
+
+// The first game as written here, with a variation where Alice drops
+const SGF: &str = "(;FF[4]GM[6]CA[UTF-8]AP[game-trees]MI[length:3][game:0][ws:0][bs:0]PW[Alice]PB[Bob]RE[W+2]
+;B[31hefe]
+;W[52lnlq]
+;B[double]
+(;W[take]
+;B[64xrmi]
+;W[double]
+;B[drop])
+(;W[drop]))
+";
+
+fn cube_actions(ms: &[Move]) -> Vec<CubeAction> {
+    ms.iter()
+        .filter_map(|m| match *m {
+            Move::Cube(CubeAction::NoDouble) => None,
+            Move::Cube(a) => Some(a),
+            _ => None,
+        })
+        .collect()
+}
+
+fn white_points(record: &MatchRecord, game: usize) -> Option<f64> {
+    record.games[game].end().scores().map(|scores| scores[&Some(true)])
+}
+
+#[test]
+fn mat_files_are_read() {
+    let r = MatchRecord::parse_mat(MAT).unwrap();
+    assert_eq!(r.length, 3);
+    assert_eq!(r.players, ("Alice".to_string(), "Bob".to_string()));
+    let scores: Vec<_> = r.games.iter().map(|g| g.score).collect();
+    assert_eq!(scores, vec![(0, 0), (2, 0), (2, 1)]);
+    // Bob's lone entry opens the first game
+    assert_eq!(r.games[0].states()[1].current_player(), Some(false));
+    assert_eq!(r.games[0].moves[0], Move::Roll((3, 1)));
+    use CubeAction::*;
+    assert_eq!(cube_actions(&r.games[0].moves), vec![Double, Take, Double, Drop]);
+    assert_eq!(white_points(&r, 0), Some(2.0));
+    // Alice is a point away, so the second game is played without the cube
+    assert_eq!(r.games[1].start.cube, None);
+    assert_eq!(r.games[1].moves.len(), 8);
+    assert_eq!(white_points(&r, 1), None);
+    assert!(r.games[2].start.cube.is_some());
+    assert_eq!(cube_actions(&r.games[2].moves), vec![Double, Drop]);
+    assert_eq!(white_points(&r, 2), Some(-1.0));
+}
+
+#[test]
+fn mat_files_are_written_as_read() {
+    let r = MatchRecord::parse_mat(MAT).unwrap();
+    let text = r.to_mat();
+    assert!(text.contains("\n  4)  Doubles => 4                 Drops\n  5)  Wins 2 points\n"));
+    assert_eq!(MatchRecord::parse_mat(&text).unwrap(), r);
+}
+
+#[test]
+fn lone_entries_belong_to_the_nearer_column() {
+    let narrow = "
+ 1 point match
+
+ Game 1
+ Alice : 0      Bob : 0
+  1)        31: 8/5 6/5
+  2) 52: 13/8 13/11
+";
+    let r = MatchRecord::parse_mat(narrow).unwrap();
+    let g = &r.games[0];
+    assert_eq!(g.states()[1].current_player(), Some(false));
+    assert_eq!(g.moves.len(), 4);
+    // A one point match starts with the Crawford game
+    assert_eq!(g.start.cube, None);
+}
+
+#[test]
+fn doubles_in_the_crawford_game_are_rejected() {
+    let crawford = MAT.replace("63: 24/18 13/10\n", "Doubles => 2\n");
+    assert!(MatchRecord::parse_mat(&crawford).is_err());
+}
+
+#[test]
+fn sgf_files_are_written_as_read() {
+    let r = MatchRecord::parse_mat(MAT).unwrap();
+    let text = r.to_sgf();
+    assert!(text.contains("RE[W+2]\n;B[31hefe]\n;W[52lnlq]\n;B[double]\n;W[take]\n"));
+    assert_eq!(MatchRecord::parse_sgf(&text).unwrap(), r);
+}
+
+#[test]
+fn sgf_files_are_read_along_the_main_line() {
+    let all = MatchRecord::parse_mat(MAT).unwrap();
+    let r = MatchRecord::parse_sgf(SGF).unwrap();
+    assert_eq!(r.length, 3);
+    assert_eq!(r.players, all.players);
+    assert_eq!(r.games, vec![all.games[0].clone()]);
+    assert_eq!(r.to_sgf(), SGF.replace("(;W[take]", ";W[take]").replace(")\n(;W[drop]))", ")"));
+}
@@ -0,0 +1,116 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Cube, Move, Point, StackHeight};
+use game_trees::game::backgammon::Location::{Bar, Board};
+use game_trees::game::backgammon::render::BoardStyle;
+
+const WHITE_OPENING: &str = "\
++13-14-15-16-17-18------19-20-21-22-23-24-+
+| O           X    |   | X              O |  X off: 0
+| O           X    |   | X              O |
+| O           X    |   | X                |
+| O                |   | X                |
+| O                |   | X                |
+|                  |BAR|                  |
+| X                |   | O                |
+| X                |   | O                |
+| X           O    |   | O                |
+| X           O    |   | O              X |
+| X           O    |   | O              X |  O off: 0
++12-11-10- 9- 8- 7------ 6- 5- 4- 3- 2- 1-+
+O White 167 pips, X Black 167 pips
+White to play 3-1";
+
+const BLACK_OPENING: &str = "\
++13-14-15-16-17-18------19-20-21-22-23-24-+
+| X           O    |   | O              X |  O off: 0
+| X           O    |   | O              X |
+| X           O    |   | O                |
+| X                |   | O                |
+| X                |   | O                |
+|                  |BAR|                  |
+| O                |   | X                |
+| O                |   | X                |
+| O           X    |   | X                |
+| O           X    |   | X              O |
+| O           X    |   | X              O |  X off: 0
++12-11-10- 9- 8- 7------ 6- 5- 4- 3- 2- 1-+
+O White 167 pips, X Black 167 pips
+Black to play 3-1";
+
+const ON_THE_BAR: &str = "\
++13-14-15-16-17-18------19-20-21-22-23-24-+
+| O           X    | X | X              O |  X off: 0
+| O           X    | X | X                |
+| O           X    |   | X                |
+| O                |   | X                |
+| O                |   | X                |
+|                  |BAR|                  |
+| X                |   | O                |
+| X                |   | O                |
+| X           O    |   | O                |
+| X           O    |   | O                |
+| X           O    | O | O                |  O off: 0
++12-11-10- 9- 8- 7------ 6- 5- 4- 3- 2- 1-+
+O White 168 pips, X Black 169 pips
+The cube is at 2 owned by black
+White to roll";
+
+const TALL_STACKS: &str = "\
++13-14-15-16-17-18------19-20-21-22-23-24-+
+|                  |   | O  O             |  O off: 3
+|                  |   | O  O             |
+|                  |   | O  O             |
+|                  |   | O                |
+|                  |   | 9                |
+|                  |BAR|                  |
+|                  |   | 7  X             |
+|                  |   | X  X             |
+|                  |   | X  X             |
+|                  |   | X  X             |
+|                  |   | X  X             |  X off: 3
++12-11-10- 9- 8- 7------ 6- 5- 4- 3- 2- 1-+
+O White 69 pips, X Black 67 pips
+The cube is at 1 in the middle
+Black to roll";
+
+fn opening(player: bool) -> Backgammon {
+    let mut s = Backgammon::new();
+    s.player = player;
+    s.apply(Move::Roll((3, 1)));
+    s
+}
+
+#[test]
+fn openings_are_drawn_from_the_side_of_the_player() {
+    assert_eq!(opening(true).diagram(BoardStyle::default()), WHITE_OPENING);
+    assert_eq!(opening(false).diagram(BoardStyle::default()), BLACK_OPENING);
+}
+
+#[test]
+fn pieces_on_the_bar_and_the_cube_are_shown() {
+    let mut s = Backgammon::with_cube();
+    s.counts[1] = (StackHeight(1), StackHeight(0));
+    s.counts[24] = (StackHeight(0), StackHeight(0));
+    s.counts[usize::from(Bar)] = (StackHeight(1), StackHeight(2));
+    s.cube = Some(Cube {
+        value: 2,
+        owner: Some(false),
+    });
+    s.player = true;
+    assert_eq!(s.diagram(BoardStyle::default()), ON_THE_BAR);
+}
+
+#[test]
+fn tall_stacks_are_counted() {
+    let counts = [
+        (Board(Point(19)), 9, 0),
+        (Board(Point(20)), 3, 0),
+        (Board(Point(6)), 0, 7),
+        (Board(Point(5)), 0, 5),
+    ];
+    let mut s = Backgammon::from_counts(&counts, false);
+    s.cube = Backgammon::with_cube().cube;
+    assert_eq!(s.diagram(BoardStyle::default()), TALL_STACKS);
+}
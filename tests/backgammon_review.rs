@@ -0,0 +1,105 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Move, Point};
+use game_trees::game::backgammon::Location::Board;
+use game_trees::game::backgammon::bearoff::TwoSided;
+use game_trees::game::backgammon::records::GameRecord;
+use game_trees::game::backgammon::review::{quality, GameReview, Quality};
+use game_trees::game::backgammon::rollout::{roll_luck, value};
+
+// White on its two, three and six points, black on its four, five and six points
+fn bearoff() -> Backgammon {
+    let mut counts = Vec::new();
+    for &(white, black) in &[(23, 4), (22, 5), (19, 6)] {
+        counts.push((Board(Point(white)), 1, 0));
+        counts.push((Board(Point(black)), 0, 1));
+    }
+    Backgammon::from_counts(&counts, true)
+}
+
+const ROLLS: [(u8, u8); 6] = [(2, 1), (3, 1), (6, 5), (4, 3), (5, 2), (6, 6)];
+
+// Plays the bearoff out with the rolls above, each side choosing the play the database
+// likes least or best
+fn play(db: &TwoSided, worst: bool) -> GameRecord {
+    let mut s = bearoff();
+    let mut record = GameRecord::new(s.clone());
+    for &roll in ROLLS.iter().cycle() {
+        if s.finished() {
+            break;
+        }
+        s.apply(Move::Roll(roll));
+        record.moves.push(Move::Roll(roll));
+        let sign = if s.player { 1.0 } else { -1.0 };
+        let mut plays: Vec<_> = s.legal_moves()
+            .into_iter()
+            .map(|m| {
+                let mut new = s.clone();
+                new.apply(m.clone());
+                (sign * value(db, &new).unwrap(), m)
+            })
+            .collect();
+        plays.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let m = if worst { plays[0].1.clone() } else { plays.last().unwrap().1.clone() };
+        s.apply(m.clone());
+        record.moves.push(m);
+    }
+    record
+}
+
+#[test]
+fn errors_are_graded_by_size() {
+    assert_eq!(quality(0.0), Quality::Good);
+    assert_eq!(quality(0.039), Quality::Good);
+    assert_eq!(quality(0.04), Quality::Doubtful);
+    assert_eq!(quality(0.079), Quality::Doubtful);
+    assert_eq!(quality(0.08), Quality::Error);
+    assert_eq!(quality(0.16), Quality::Blunder);
+    assert_eq!(quality(1.0), Quality::Blunder);
+}
+
+#[test]
+fn mistakes_are_counted_and_rated() {
+    let db = TwoSided::generate(3);
+    let review = GameReview::new(&play(&db, true), &db);
+    let (ref white, ref black) = review.summaries;
+    assert_eq!((white.decisions, black.decisions), (2, 1));
+    for d in &review.decisions {
+        assert!(d.error() >= 0.0);
+        assert_eq!(d.quality, quality(d.error()));
+    }
+    // The first play of each side is a blunder, the rest cost nothing or are forced
+    assert_eq!((white.blunders, black.blunders), (1, 1));
+    let black_error = review.decisions.iter().filter(|d| !d.player).map(|d| d.error()).sum::<f64>();
+    assert!((black.total_error - black_error).abs() < 1e-12);
+    assert!((black.rating() - 500.0 * black_error).abs() < 1e-9);
+    assert!((white.rating() - 250.0 * white.total_error).abs() < 1e-9);
+    assert!(review.to_string().contains("White: Blunder, played 3/1 2/1 (+0.046) instead of 6/5 2/off"));
+    // The best play costs nothing
+    let review = GameReview::new(&play(&db, false), &db);
+    for summary in &[&review.summaries.0, &review.summaries.1] {
+        assert_eq!((summary.total_error, summary.rating()), (0.0, 0.0));
+    }
+}
+
+#[test]
+fn luck_is_summed_for_each_player() {
+    let db = TwoSided::generate(3);
+    let review = GameReview::new(&play(&db, true), &db);
+    for &(player, ref summary) in &[(true, &review.summaries.0), (false, &review.summaries.1)] {
+        let luck: f64 = review.rolls.iter().filter(|l| l.player == player).map(|l| l.luck).sum();
+        assert!((summary.luck - luck).abs() < 1e-12);
+    }
+    assert_eq!(review.rolls.iter().map(|l| l.roll).collect::<Vec<_>>(), &ROLLS[..review.rolls.len()]);
+    // Luck is measured against the average roll
+    let s = bearoff();
+    let mut average = 0.0;
+    for x in 1..7 {
+        for y in 1..(x + 1) {
+            let chance = if x == y { 1.0 / 36.0 } else { 2.0 / 36.0 };
+            average += chance * roll_luck(&db, &s, (x, y));
+        }
+    }
+    assert!(average.abs() < 1e-12);
+}
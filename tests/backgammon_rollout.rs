@@ -0,0 +1,84 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, CubeAction, Move, Point};
+use game_trees::game::backgammon::Location::Board;
+use game_trees::game::backgammon::bearoff::TwoSided;
+use game_trees::game::backgammon::rollout::{rollout, rollout_moves, RolloutSettings};
+use std::sync::Arc;
+
+// White on roll with pieces on the given points, black on its four and five points
+fn bearoff(white: &[u8]) -> Backgammon {
+    let mut counts = vec![(Board(Point(4)), 0, 1), (Board(Point(5)), 0, 1)];
+    counts.extend(white.iter().map(|&point| (Board(Point(25 - point)), 1, 0)));
+    Backgammon::from_counts(&counts, true)
+}
+
+fn settings(threads: usize) -> RolloutSettings {
+    RolloutSettings {
+        trials: 100,
+        threads: threads,
+        ..RolloutSettings::default()
+    }
+}
+
+#[test]
+fn results_do_not_depend_on_the_threads() {
+    let evaluator = Arc::new(TwoSided::generate(3));
+    let s = bearoff(&[6, 6, 5]);
+    let one = rollout(&s, evaluator.clone(), &settings(1)).unwrap();
+    assert_eq!(one.trials, 100);
+    assert_eq!(rollout(&s, evaluator.clone(), &settings(3)).unwrap(), one);
+    let mut truncated = settings(1);
+    truncated.truncation = Some(1);
+    let one = rollout(&s, evaluator.clone(), &truncated).unwrap();
+    truncated.threads = 4;
+    assert_eq!(rollout(&s, evaluator, &truncated).unwrap(), one);
+}
+
+#[test]
+fn certain_wins_have_no_error() {
+    let evaluator = Arc::new(TwoSided::generate(3));
+    let estimate = rollout(&bearoff(&[1, 1]), evaluator, &settings(2)).unwrap();
+    assert!((estimate.equity - 1.0).abs() < 1e-9);
+    assert!(estimate.standard_error < 1e-9);
+}
+
+#[test]
+fn doubles_are_rolled_out_for_the_player_answering_them() {
+    let evaluator = Arc::new(TwoSided::generate(3));
+    let mut s = bearoff(&[1, 1]);
+    s.cube = Backgammon::with_cube().cube;
+    s.player = false;
+    s.apply(Move::Roll((2, 1)));
+    let m = s.legal_moves()[0].clone();
+    s.apply(m);
+    // White is sure to win and doubles, black would lose two points by taking
+    s.apply(Move::Cube(CubeAction::Double));
+    assert_eq!(s.current_player(), Some(false));
+    let estimate = rollout(&s, evaluator, &settings(1)).unwrap();
+    assert!((estimate.equity + 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn plays_are_ranked_best_first() {
+    let evaluator = Arc::new(TwoSided::generate(3));
+    let mut s = bearoff(&[6, 5, 1]);
+    s.apply(Move::Roll((2, 1)));
+    let ranked = rollout_moves(&s, evaluator, &settings(2)).unwrap();
+    assert_eq!(ranked.len(), s.legal_moves().len());
+    assert!(ranked.windows(2).all(|w| w[0].1.equity >= w[1].1.equity));
+}
+
+#[test]
+fn rollouts_need_trials() {
+    let evaluator = Arc::new(TwoSided::generate(3));
+    let none = RolloutSettings {
+        trials: 0,
+        ..RolloutSettings::default()
+    };
+    assert!(rollout(&bearoff(&[6, 6, 5]), evaluator.clone(), &none).is_err());
+    let mut s = bearoff(&[6, 6, 5]);
+    s.apply(Move::Roll((2, 1)));
+    assert!(rollout_moves(&s, evaluator, &none).is_err());
+}
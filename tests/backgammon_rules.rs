@@ -0,0 +1,159 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::{Backgammon, Move, Point, Roll, SingleMove, StackHeight};
+use game_trees::game::backgammon::Location::{self, Bar, Board, Home};
+
+// Location, white pieces, black pieces
+type Layout = &'static [(Location, u8, u8)];
+
+struct Case {
+    name: &'static str,
+    layout: Layout,
+    player: bool,
+    roll: Roll,
+    legal: &'static [&'static [SingleMove]],
+}
+
+const CASES: &'static [Case] = &[
+    Case {
+        name: "bearing off with a higher die only from the rearmost point",
+        layout: &[(Board(Point(6)), 0, 15), (Board(Point(20)), 1, 0), (Board(Point(23)), 1, 0), (Home, 13, 0)],
+        player: true,
+        roll: (6, 3),
+        legal: &[
+            &[(Board(Point(20)), 3), (Board(Point(23)), 6)],
+            &[(Board(Point(20)), 6), (Board(Point(23)), 3)],
+        ],
+    },
+    Case {
+        name: "bearing off with a higher die for black",
+        layout: &[(Board(Point(2)), 0, 1), (Board(Point(5)), 0, 1), (Board(Point(13)), 15, 0), (Home, 0, 13)],
+        player: false,
+        roll: (6, 3),
+        legal: &[
+            &[(Board(Point(2)), 3), (Board(Point(5)), 6)],
+            &[(Board(Point(2)), 6), (Board(Point(5)), 3)],
+        ],
+    },
+    Case {
+        name: "exact bear off is preferred to overshooting",
+        layout: &[(Board(Point(6)), 0, 15), (Board(Point(19)), 1, 0), (Board(Point(24)), 1, 0), (Home, 13, 0)],
+        player: true,
+        roll: (6, 2),
+        legal: &[
+            &[(Board(Point(19)), 2), (Board(Point(21)), 6)],
+            &[(Board(Point(19)), 6), (Board(Point(24)), 2)],
+        ],
+    },
+    Case {
+        name: "the higher die must be used when only one die can be",
+        layout: &[(Board(Point(1)), 1, 0), (Board(Point(2)), 0, 13), (Board(Point(12)), 0, 2), (Home, 14, 0)],
+        player: true,
+        roll: (6, 5),
+        legal: &[&[(Board(Point(1)), 6)]],
+    },
+    Case {
+        name: "the lower die is used when the higher one cannot be",
+        layout: &[
+            (Board(Point(1)), 1, 0),
+            (Board(Point(7)), 0, 2),
+            (Board(Point(11)), 0, 11),
+            (Board(Point(12)), 0, 2),
+            (Home, 14, 0),
+        ],
+        player: true,
+        roll: (6, 5),
+        legal: &[&[(Board(Point(1)), 5)]],
+    },
+    Case {
+        name: "both dice must be used when possible",
+        layout: &[(Board(Point(1)), 1, 0), (Board(Point(2)), 0, 13), (Board(Point(7)), 0, 2), (Home, 14, 0)],
+        player: true,
+        roll: (6, 5),
+        legal: &[&[(Board(Point(1)), 5), (Board(Point(6)), 6)]],
+    },
+    Case {
+        name: "both dice must be used when possible for black",
+        layout: &[(Board(Point(8)), 2, 0), (Board(Point(13)), 0, 1), (Board(Point(20)), 13, 0), (Home, 0, 14)],
+        player: false,
+        roll: (6, 5),
+        legal: &[&[(Board(Point(7)), 5), (Board(Point(13)), 6)]],
+    },
+    Case {
+        name: "pieces on the bar must enter first",
+        layout: &[(Board(Point(6)), 0, 2), (Board(Point(12)), 14, 0), (Board(Point(24)), 0, 13), (Bar, 1, 0)],
+        player: true,
+        roll: (6, 5),
+        legal: &[
+            &[(Board(Point(5)), 6), (Bar, 5)],
+            &[(Board(Point(12)), 6), (Bar, 5)],
+        ],
+    },
+    Case {
+        name: "no move when the bar is closed",
+        layout: &[
+            (Board(Point(1)), 0, 2),
+            (Board(Point(2)), 0, 2),
+            (Board(Point(3)), 0, 2),
+            (Board(Point(4)), 0, 3),
+            (Board(Point(5)), 0, 3),
+            (Board(Point(6)), 0, 3),
+            (Board(Point(12)), 14, 0),
+            (Bar, 1, 0),
+        ],
+        player: true,
+        roll: (4, 4),
+        legal: &[&[]],
+    },
+    Case {
+        name: "doubles are played as far as possible",
+        layout: &[(Board(Point(1)), 1, 0), (Board(Point(2)), 0, 13), (Board(Point(13)), 0, 2), (Home, 14, 0)],
+        player: true,
+        roll: (4, 4),
+        legal: &[&[(Board(Point(1)), 4), (Board(Point(5)), 4)]],
+    },
+];
+
+fn position(layout: Layout, player: bool, roll: Roll) -> Backgammon {
+    let mut s = Backgammon::new();
+    for count in &mut s.counts {
+        *count = (StackHeight(0), StackHeight(0));
+    }
+    for &(l, white, black) in layout {
+        s.counts[usize::from(l)] = (StackHeight(white), StackHeight(black));
+    }
+    s.player = player;
+    s.apply(Move::Roll(roll));
+    s
+}
+
+fn pieces(s: &Backgammon, p: bool) -> u32 {
+    s.counts
+        .iter()
+        .map(|&(w, b)| if p { w.0 as u32 } else { b.0 as u32 })
+        .sum()
+}
+
+#[test]
+fn legal_moves_in_tricky_positions() {
+    for case in CASES {
+        let s = position(case.layout, case.player, case.roll);
+        let mut expected: Vec<_> = case.legal.iter().map(|ms| Move::Play(ms.to_vec())).collect();
+        expected.sort();
+        assert_eq!(s.legal_moves(), expected, "{}", case.name);
+    }
+}
+
+#[test]
+fn legal_moves_keep_pieces_on_the_board() {
+    for case in CASES {
+        let s = position(case.layout, case.player, case.roll);
+        for m in s.legal_moves() {
+            let mut new = s.clone();
+            new.apply(m);
+            assert_eq!(pieces(&new, true), 15, "{}", case.name);
+            assert_eq!(pieces(&new, false), 15, "{}", case.name);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::connect_four::ConnectFour;
+use game_trees::mcts_hashtable::MctsTable;
+use game_trees::solver::Solver;
+
+fn position(moves: &str) -> ConnectFour {
+    ConnectFour::from_moves(7, 6, moves).unwrap()
+}
+
+#[test]
+fn fours_win_in_every_direction() {
+    for moves in &["4455667", "4343434", "12233434544", "76655454344"] {
+        let s = position(moves);
+        assert_eq!(s.winner(), Some(false), "{}", moves);
+        assert!(s.finished());
+    }
+    // Lines don't run from the top of one column to the bottom of the next
+    assert_eq!(position("21717117161").winner(), None);
+}
+
+#[test]
+fn full_columns_take_no_more_stones() {
+    let s = position("444444");
+    assert!(!s.legal_moves().contains(&3));
+    assert_eq!(s.legal_moves().len(), 6);
+    assert!(ConnectFour::from_moves(7, 6, "4444444").is_err());
+    assert!(ConnectFour::with_size(8, 8).is_err());
+    assert!(ConnectFour::with_size(8, 7).is_ok());
+}
+
+// Positions with their values for the player to move
+#[test]
+fn solver_plays_perfectly() {
+    // Small boards are drawn, as John Tromp found
+    for &(width, height) in &[(4, 4), (5, 4), (4, 5)] {
+        let s = ConnectFour::with_size(width, height).unwrap();
+        assert_eq!(Solver::with_range(-1.0, 1.0).value(&s), 0.0, "{}x{}", width, height);
+    }
+    let known = [
+        // Three in a row open at both ends, on the move and facing it
+        ("445566", 1.0),
+        ("44556", -1.0),
+        ("44553", -1.0),
+        // Games already won
+        ("4343434", -1.0),
+    ];
+    for &(moves, value) in &known {
+        let s = position(moves);
+        assert_eq!(Solver::with_range(-1.0, 1.0).value(&s), value, "{}", moves);
+    }
+    // Winning at once is among the moves keeping the win
+    let s = position("445566");
+    let best = Solver::with_range(-1.0, 1.0).best_moves(&s);
+    assert!(best.contains(&2) && best.contains(&6));
+}
+
+#[test]
+fn search_finds_wins_and_blocks() {
+    // The win, blocking the only threat, and blocking before it is made
+    for &(moves, best) in &[("445566", [2, 6]), ("17273", [3, 3]), ("445", [2, 5])] {
+        let s = position(moves);
+        let mut table = MctsTable::with_state(s.clone());
+        for _ in 0..2000 {
+            table.playout(&s, 50);
+        }
+        let m = table.best_choice(&s).unwrap();
+        assert!(best.contains(&m), "{} after {}", m + 1, moves);
+    }
+}
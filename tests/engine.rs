@@ -0,0 +1,109 @@
+extern crate game_trees;
+
+use game_trees::engine::{Budget, Engine, EngineSettings};
+use game_trees::game::{Evaluator, GameState, ScoreBoard};
+use game_trees::game::nim::Nim;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+fn playouts(n: u32) -> Budget {
+    Budget {
+        playouts: Some(n),
+        time: None,
+    }
+}
+
+#[test]
+fn best_move_stops_on_a_playout_budget() {
+    // Reaching 100 wins at once
+    let s = Nim::with_total(95, false);
+    let engine = Engine::new(s.clone(), EngineSettings::default());
+    assert_eq!(engine.best_move(playouts(500)), Some(5));
+    engine.stop();
+    assert!(engine.playouts(&s) >= 500);
+    // Playouts made earlier count towards the budget
+    let before = engine.playouts(&s);
+    assert!(engine.best_move(playouts(100)).is_some());
+    engine.stop();
+    assert!(engine.playouts(&s) - before < 500);
+}
+
+#[test]
+fn opponent_moved_keeps_the_subtree_of_the_move() {
+    let s = Nim::with_total(80, false);
+    let engine = Engine::new(s.clone(), EngineSettings::default());
+    engine.best_move(playouts(2000));
+    engine.stop();
+    let child = |m: u32| {
+        let mut new = s.clone();
+        new.apply(m);
+        new
+    };
+    let kept = engine.playouts(&child(3));
+    assert!(kept > 0);
+    assert!(engine.table().nodes.contains_key(&child(4)));
+    engine.opponent_moved(3);
+    engine.stop();
+    assert!(engine.position() == child(3));
+    assert!(engine.playouts(&child(3)) >= kept);
+    // Nothing reachable only through the other moves is left
+    assert!(!engine.table().nodes.contains_key(&child(4)));
+}
+
+// Dropping the engine on another thread, which reports back once the workers are joined
+fn dropped_in_time(engine: Engine<Nim>) -> bool {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        drop(engine);
+        tx.send(()).unwrap();
+    });
+    rx.recv_timeout(Duration::from_secs(10)).is_ok()
+}
+
+#[test]
+fn drop_joins_the_workers() {
+    let settings = EngineSettings {
+        threads: 4,
+        ..EngineSettings::default()
+    };
+    // Idle workers are waiting to be told of something to search
+    let idle = Engine::new(Nim::new(), settings.clone());
+    assert!(dropped_in_time(idle));
+    let busy = Engine::new(Nim::new(), settings);
+    busy.ponder(Nim::new());
+    assert!(dropped_in_time(busy));
+}
+
+// As slow to evaluate positions as a network, without keeping the processor busy
+struct Slow;
+
+impl Evaluator<Nim> for Slow {
+    fn evaluate(&self, _: &Nim) -> Option<ScoreBoard<Nim>> {
+        thread::sleep(Duration::from_millis(1));
+        Some(Nim::players().into_iter().map(|p| (p, 0.0)).collect())
+    }
+}
+
+#[test]
+fn more_threads_make_more_playouts() {
+    let playouts_in_time = |threads| {
+        let s = Nim::new();
+        let settings = EngineSettings {
+            threads: threads,
+            evaluator: Some(Arc::new(Slow)),
+            ..EngineSettings::default()
+        };
+        let engine = Engine::new(s.clone(), settings);
+        engine.best_move(Budget {
+            playouts: None,
+            time: Some(Duration::from_millis(500)),
+        });
+        engine.stop();
+        engine.playouts(&s)
+    };
+    let one = playouts_in_time(1);
+    let two = playouts_in_time(2);
+    assert!(one > 0);
+    assert!(two >= one, "{} playouts with two threads, {} with one", two, one);
+}
@@ -0,0 +1,73 @@
+extern crate game_trees;
+
+use game_trees::game::{GameState, Notation};
+use game_trees::game::mnk::MnkGame;
+use game_trees::mcts_hashtable::MctsTable;
+use game_trees::solver::Solver;
+
+fn position(size: (u8, u8, u8), moves: &str) -> MnkGame {
+    let mut s = MnkGame::with_size(size.0, size.1, size.2).unwrap();
+    for text in moves.split_whitespace() {
+        let m = s.parse_move(text).unwrap();
+        s.apply(m);
+    }
+    s
+}
+
+#[test]
+fn lines_win_in_every_direction() {
+    for moves in &["a1 a2 b1 b2 c1", "a1 b1 a2 b2 a3", "a1 b1 b2 c1 c3", "c1 a1 b2 a2 a3"] {
+        let s = position((3, 3, 3), moves);
+        assert_eq!(s.winner(), Some(false), "{}", moves);
+        assert!(s.finished());
+    }
+    // Lines don't run from the end of one row into the next
+    let s = position((3, 3, 3), "c1 b2 a2 c2");
+    assert_eq!(s.winner(), None);
+    let s = position((15, 15, 5), "k8 a1 l8 a2 m8 a3 n8 a4");
+    assert_eq!(s.winner(), None);
+    let s = position((15, 15, 5), "k8 a1 l8 a2 m8 a3 n8 a4 o8");
+    assert_eq!(s.winner(), Some(false));
+}
+
+#[test]
+fn full_boards_are_drawn() {
+    let s = position((3, 3, 3), "a1 b1 c1 b2 a2 a3 b3 c2 c3");
+    assert!(s.finished());
+    assert_eq!(s.winner(), None);
+    assert_eq!(s.scores().unwrap()[&false], 0.0);
+}
+
+#[test]
+fn solver_finds_known_values() {
+    // Tic-tac-toe is a draw, every first move keeping it one
+    let s = MnkGame::new();
+    let mut solver = Solver::new();
+    assert_eq!(solver.value(&s), 0.0);
+    assert_eq!(solver.best_moves(&s).len(), 9);
+    // Any two cells of a 2 by 2 board are in line
+    assert_eq!(Solver::new().value(&position((2, 2, 2), "")), 1.0);
+    // Three in a row can be forced on a board four wide
+    assert_eq!(Solver::new().value(&position((4, 3, 3), "")), 1.0);
+    // Only the centre holds the draw against a corner
+    let mut moves = solver.best_moves(&position((3, 3, 3), "a1"));
+    moves.sort();
+    assert_eq!(moves, vec![(1, 1)]);
+}
+
+#[test]
+fn search_agrees_with_solver() {
+    let mut solver = Solver::new();
+    for moves in &["", "a1", "b2 a1", "a1 b2 c3", "a1 c1 b2"] {
+        let s = position((3, 3, 3), moves);
+        let mut table = MctsTable::with_state(s.clone());
+        for _ in 0..10000 {
+            table.playout(&s, 20);
+        }
+        let best = table.best_choice(&s).unwrap();
+        assert!(solver.best_moves(&s).contains(&best), "{} after {}", s.format_move(&best), moves);
+        let meta = &table.nodes[&s];
+        let expected = meta.scoreboard[&s.current_player()] / meta.playouts as f64;
+        assert!((expected - solver.value(&s)).abs() < 0.3, "expected {} after {}", expected, moves);
+    }
+}
@@ -0,0 +1,82 @@
+extern crate game_trees;
+
+use game_trees::game::nim::Nim;
+use game_trees::options::{engine_config, value, Mode, Options};
+use game_trees::play::Kind;
+use std::time::Duration;
+
+// Options with the game knowing only "--size", which takes a value
+fn parse(args: &[&str]) -> Result<Options, String> {
+    let mut args = args.iter().map(|arg| arg.to_string());
+    Options::parse(&mut args, |name, args| {
+        if name != "--size" {
+            return Ok(false);
+        }
+        value(args, name)?;
+        Ok(true)
+    })
+}
+
+#[test]
+fn options_default_to_a_human_moving_first_against_the_engine() {
+    let options = parse(&[]).unwrap();
+    assert_eq!(options, Options::default());
+    assert_eq!(options.mode, Mode::HumanEngine);
+    assert_eq!(
+        options.kinds::<Nim>(vec![true, false]),
+        vec![(true, Kind::Human), (false, Kind::Engine)]
+    );
+}
+
+#[test]
+fn modes_and_sides_are_read() {
+    let options = parse(&["--mode", "engine-engine"]).unwrap();
+    assert_eq!(options.mode, Mode::EngineEngine);
+    assert_eq!(
+        options.kinds::<Nim>(vec![true, false]),
+        vec![(true, Kind::Engine), (false, Kind::Engine)]
+    );
+    let options = parse(&["--mode", "human-human"]).unwrap();
+    assert_eq!(
+        options.kinds::<Nim>(vec![true, false]),
+        vec![(true, Kind::Human), (false, Kind::Human)]
+    );
+    let options = parse(&["--first", "engine"]).unwrap();
+    assert!(!options.human_first);
+    assert_eq!(
+        options.kinds::<Nim>(vec![true, false]),
+        vec![(true, Kind::Engine), (false, Kind::Human)]
+    );
+    assert!(parse(&["--mode", "engine-human"]).is_err());
+    assert!(parse(&["--first", "nobody"]).is_err());
+    assert!(parse(&["--mode"]).is_err());
+}
+
+#[test]
+fn numbers_are_read() {
+    let options = parse(&["--playouts", "800", "--time", "1.5", "--threads", "4", "--exploration", "2"]).unwrap();
+    assert_eq!(options.playouts, Some(800));
+    assert_eq!(options.time, Duration::from_millis(1500));
+    assert_eq!(options.threads, 4);
+    assert_eq!(options.exploration, 2.0);
+    assert!(parse(&["--playouts", "many"]).is_err());
+}
+
+#[test]
+fn unknown_options_are_left_to_the_game() {
+    assert_eq!(parse(&["--size", "7,6", "--threads", "2"]).unwrap().threads, 2);
+    assert_eq!(parse(&["--cube"]), Err("Unknown option --cube".to_string()));
+    assert!(parse(&["--size"]).is_err());
+}
+
+#[test]
+fn engine_configs_are_read() {
+    let e = engine_config::<Nim>("name=wide,playouts=800,exploration=2,max-its=50", 1).unwrap();
+    assert_eq!((e.name.as_str(), e.playouts, e.max_its, e.exploration), ("wide", 800, 50, 2.0));
+    // Unnamed engines are numbered
+    let e = engine_config::<Nim>("playouts=100", 3).unwrap();
+    assert_eq!((e.name.as_str(), e.playouts), ("engine 3", 100));
+    assert!(engine_config::<Nim>("playouts=lots", 1).is_err());
+    assert!(engine_config::<Nim>("speed=8", 1).is_err());
+    assert!(engine_config::<Nim>("name", 1).is_err());
+}
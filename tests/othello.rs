@@ -0,0 +1,110 @@
+extern crate game_trees;
+
+use game_trees::game::{GameState, Notation};
+use game_trees::game::othello::{Move, Othello};
+
+// Positions reached after exactly so many moves, passes counting as moves
+fn perft(s: &Othello, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    s.possible_moves()
+        .into_iter()
+        .map(|m| {
+            let mut new = s.clone();
+            new.apply(m);
+            perft(&new, depth - 1)
+        })
+        .sum()
+}
+
+// The bit of a square such as "d3"
+fn square(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    1 << ((bytes[1] - b'1') * 8 + bytes[0] - b'a')
+}
+
+#[test]
+fn opening_position() {
+    let s = Othello::new();
+    assert_eq!(s.at(3, 3), Some(true));
+    assert_eq!(s.at(4, 4), Some(true));
+    assert_eq!(s.at(3, 4), Some(false));
+    assert_eq!(s.at(4, 3), Some(false));
+    assert_eq!(s.counts(), (2, 2));
+    assert_eq!(s.current_player(), false);
+    let mut moves: Vec<String> = s.legal_moves().iter().map(|m| s.format_move(m)).collect();
+    moves.sort();
+    assert_eq!(moves, ["c4", "d3", "e6", "f5"]);
+}
+
+#[test]
+fn perft_matches_known_counts() {
+    let counts = [4, 12, 56, 244, 1396, 8200, 55092, 390216];
+    let s = Othello::new();
+    for (depth, &count) in counts.iter().enumerate() {
+        assert_eq!(perft(&s, depth as u32 + 1), count, "depth {}", depth + 1);
+    }
+}
+
+#[test]
+fn discs_flip_in_every_direction() {
+    // White surrounding d4, each line ending in a black disc
+    let mut white = 0;
+    let mut black = 0;
+    for &(near, far) in &[
+        ("c4", "b4"),
+        ("e4", "f4"),
+        ("d3", "d2"),
+        ("d5", "d6"),
+        ("c3", "b2"),
+        ("e5", "f6"),
+        ("e3", "f2"),
+        ("c5", "b6"),
+    ] {
+        white |= square(near);
+        black |= square(far);
+    }
+    let mut s = Othello::with_discs(black, white, false);
+    s.apply(s.parse_move("d4").unwrap());
+    assert_eq!(s.counts(), (17, 0));
+    assert_eq!(s.current_player(), true);
+}
+
+#[test]
+fn lines_dont_wrap_around_the_edge() {
+    // Black on h3 and White on a4 make no line across the edge to b4
+    let s = Othello::with_discs(square("h3"), square("a4"), false);
+    assert!(s.parse_move("b4").is_err());
+}
+
+#[test]
+fn players_without_moves_pass() {
+    // Black can't flank White's a1, White can flank Black's b1 from c1
+    let s = Othello::with_discs(square("b1"), square("a1"), false);
+    assert!(!s.finished());
+    assert_eq!(s.legal_moves(), [Move::Pass]);
+    assert_eq!(perft(&s, 2), 1);
+    let mut s = s;
+    s.apply(s.parse_move("pass").unwrap());
+    assert_eq!(s.current_player(), true);
+    assert_eq!(s.legal_moves().len(), 1);
+    s.apply(s.parse_move("c1").unwrap());
+    // Black has no discs left, so neither side can move
+    assert!(s.finished());
+    assert!(s.possible_moves().is_empty());
+    let scores = s.scores().unwrap();
+    assert_eq!(scores[&false], -3.0);
+    assert_eq!(scores[&true], 3.0);
+}
+
+#[test]
+fn scores_are_disc_differences() {
+    // A game ending with empty squares, neither side able to flank the other
+    let s = Othello::with_discs(square("a1") | square("b1") | square("c1"), square("h8"), true);
+    assert!(s.finished());
+    let scores = s.scores().unwrap();
+    assert_eq!(scores[&false], 2.0);
+    assert_eq!(scores[&true], -2.0);
+    assert_eq!(Othello::new().scores(), None);
+}
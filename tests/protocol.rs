@@ -0,0 +1,131 @@
+extern crate game_trees;
+
+use game_trees::game::Codec;
+use game_trees::game::backgammon::Backgammon;
+use game_trees::game::nim::Nim;
+use game_trees::protocol::Protocol;
+use std::io;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Output kept where the test can read it while the protocol writes to it
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Output {
+    // The lines written since last asked
+    fn take(&self) -> Vec<String> {
+        let bytes: Vec<u8> = self.0.lock().unwrap().drain(..).collect();
+        String::from_utf8(bytes).unwrap().lines().map(|line| line.to_string()).collect()
+    }
+}
+
+fn send<G: Codec + 'static, W: Write + Send + 'static>(protocol: &mut Protocol<G, W>, line: &str) {
+    assert!(protocol.command(line).unwrap(), "{}", line);
+}
+
+#[test]
+fn positions_are_set_and_shown() {
+    let out = Output::default();
+    let mut protocol = Protocol::<Nim, _>::new(out.clone());
+    send(&mut protocol, "show");
+    send(&mut protocol, "position 93 1");
+    send(&mut protocol, "show");
+    send(&mut protocol, "move 4");
+    send(&mut protocol, "show");
+    send(&mut protocol, "position start");
+    send(&mut protocol, "show");
+    assert_eq!(out.take(), ["position 0 0", "position 93 1", "position 97 0", "position 0 0"]);
+}
+
+#[test]
+fn legal_moves_one_per_line() {
+    let out = Output::default();
+    let mut protocol = Protocol::<Nim, _>::new(out.clone());
+    send(&mut protocol, "position 97 0");
+    send(&mut protocol, "legal");
+    assert_eq!(out.take(), ["1", "2", "3"]);
+    send(&mut protocol, "move 3");
+    send(&mut protocol, "legal");
+    assert!(out.take().is_empty());
+}
+
+#[test]
+fn failures_are_answered_with_errors() {
+    let out = Output::default();
+    let mut protocol = Protocol::<Nim, _>::new(out.clone());
+    for line in &[
+        "fly",
+        "move 11",
+        "position 101 0",
+        "position 5",
+        "dice 31",
+        "go playouts",
+        "go moves 10",
+    ] {
+        send(&mut protocol, line);
+        let reply = out.take();
+        assert_eq!(reply.len(), 1, "{}", line);
+        assert!(reply[0].starts_with("error "), "{} gave {}", line, reply[0]);
+    }
+    send(&mut protocol, "position 100 1");
+    send(&mut protocol, "go");
+    assert_eq!(out.take(), ["error The game is over"]);
+    send(&mut protocol, "isready");
+    assert_eq!(out.take(), ["readyok"]);
+    assert!(!protocol.command("quit").unwrap());
+}
+
+#[test]
+fn search_ends_with_the_best_move() {
+    let out = Output::default();
+    let mut protocol = Protocol::<Nim, _>::new(out.clone());
+    send(&mut protocol, "position 92 0");
+    send(&mut protocol, "go playouts 2000");
+    // The search ends on its own once the budget is spent
+    let mut lines = Vec::new();
+    for _ in 0..100 {
+        lines.extend(out.take());
+        if lines.last().map_or(false, |line| line.starts_with("bestmove")) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let info: Vec<_> = lines[lines.len() - 2].split_whitespace().collect();
+    assert_eq!(&info[..2], ["info", "playouts"]);
+    assert!(info[2].parse::<u32>().unwrap() >= 2000);
+    // Reaching 100 wins
+    assert_eq!(lines.last().unwrap(), "bestmove 8");
+}
+
+#[test]
+fn shown_backgammon_positions_read_back_the_same() {
+    let out = Output::default();
+    let mut protocol = Protocol::<Backgammon, _>::new(out.clone());
+    send(&mut protocol, "show");
+    let start = out.take()[0].clone();
+    send(&mut protocol, "dice 31");
+    send(&mut protocol, "move 8/5 6/5");
+    send(&mut protocol, "show");
+    let moved = out.take()[0].clone();
+    // A cubeless game stays cubeless, with no cube actions to take
+    for position in &[start, moved] {
+        send(&mut protocol, position);
+        send(&mut protocol, "show");
+        assert_eq!(&out.take()[0], position);
+    }
+    send(&mut protocol, "legal");
+    assert!(out.take().iter().all(|m| !m.contains("double")));
+}
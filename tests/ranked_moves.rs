@@ -0,0 +1,41 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::nim::Nim;
+use game_trees::mcts_hashtable::MctsTable;
+
+#[test]
+fn moves_are_ranked_by_playouts_with_their_scores() {
+    // Adding 8 reaches 100 and wins
+    let s = Nim::with_total(92, false);
+    let mut table = MctsTable::with_state(s.clone());
+    for _ in 0..2000 {
+        table.playout(&s, 20);
+    }
+    let ranked = table.ranked_moves(&s);
+    assert_eq!(ranked.len(), s.legal_moves().len());
+    assert_eq!(Some(ranked[0].0), table.best_choice(&s));
+    assert_eq!((ranked[0].0, ranked[0].2), (8, 1.0));
+    assert!(ranked.windows(2).all(|w| w[0].1 > w[1].1 || (w[0].1 == w[1].1 && w[0].2 >= w[1].2)));
+    // Playouts are those of the position the move leads to, however it was reached
+    for &(m, playouts, score) in &ranked {
+        let mut new = s.clone();
+        new.apply(m);
+        let meta = &table.nodes[&new];
+        assert_eq!(playouts, meta.playouts);
+        // Scores are for the player making the move
+        assert_eq!(score, meta.scoreboard[&false] / meta.playouts as f64);
+        assert!(score <= 1.0);
+    }
+}
+
+#[test]
+fn untried_moves_are_left_out() {
+    let s = Nim::with_total(50, true);
+    let mut table = MctsTable::with_state(s.clone());
+    assert!(table.ranked_moves(&s).is_empty());
+    table.playout(&s, 20);
+    assert_eq!(table.ranked_moves(&s).len(), 1);
+    assert!(table.ranked_moves(&Nim::with_total(51, false)).len() <= 1);
+    assert!(table.ranked_moves(&Nim::with_total(3, false)).is_empty());
+}
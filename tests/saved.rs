@@ -0,0 +1,45 @@
+extern crate game_trees;
+
+use game_trees::game::{GameState, Notation};
+use game_trees::game::backgammon::Backgammon;
+use game_trees::mcts_hashtable::MctsTable;
+use game_trees::saved::SavedGame;
+
+#[test]
+fn saved_games_resume_where_they_stopped() {
+    let start = Backgammon::with_cube();
+    let mut s = start.clone();
+    let mut moves = Vec::new();
+    for text in &["31", "8/5 6/5", "no double", "64", "24/18 13/9"] {
+        let m = s.parse_move(text).unwrap();
+        moves.push(m.clone());
+        s.apply(m);
+    }
+    let mut table = MctsTable::with_state(s.clone());
+    for _ in 0..500 {
+        table.playout(&s, 100);
+    }
+    let args = vec!["backgammon".to_string(), "--cube".to_string()];
+    let saved = SavedGame::new(&args, &start, &moves, Some(&table));
+
+    let path = std::env::temp_dir().join(format!("game-trees-saved-test-{}.sav", std::process::id()));
+    saved.save(&path).unwrap();
+    let loaded = SavedGame::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, saved);
+
+    let (replayed, position) = loaded.replay(&start).unwrap();
+    assert!(replayed == moves);
+    assert!(position == s);
+    let mut restored = MctsTable::with_state(position.clone());
+    loaded.restore(&mut restored, &position).unwrap();
+    assert_eq!(restored.nodes.len(), table.nodes.len());
+    assert_eq!(restored.nodes[&s].playouts, 500);
+    assert_eq!(restored.write_nodes(&s), saved.nodes);
+}
+
+#[test]
+fn other_files_are_not_saved_games() {
+    assert!(SavedGame::parse("1\n2\n").is_err());
+    assert!(SavedGame::parse("game-trees saved game\nmove 31\n").is_err());
+}
@@ -0,0 +1,40 @@
+extern crate game_trees;
+
+use game_trees::game::GameState;
+use game_trees::game::backgammon::Backgammon;
+use game_trees::game::nim::Nim;
+use game_trees::mcts_hashtable::MctsTable;
+
+// Playouts, scores and choices of every state searched
+fn search<G: GameState>(seed: [u32; 4], s: &G) -> Vec<(u32, Vec<u64>, Option<G::Move>)> {
+    let mut table = MctsTable::with_state(s.clone());
+    table.seed(seed);
+    for _ in 0..200 {
+        table.playout(s, 50);
+    }
+    let mut v: Vec<_> = table
+        .nodes
+        .iter()
+        .map(|(state, meta)| {
+            let mut scores: Vec<_> = meta.scoreboard.values().map(|score| score.to_bits()).collect();
+            scores.sort();
+            (meta.playouts, scores, table.best_choice(state))
+        })
+        .collect();
+    v.sort_by_key(|&(playouts, ref scores, _)| (playouts, scores.clone()));
+    v
+}
+
+#[test]
+fn same_seed_gives_same_search() {
+    let s = Backgammon::new();
+    assert!(search([1, 2, 3, 4], &s) == search([1, 2, 3, 4], &s));
+    let s = Nim::new();
+    assert!(search([5, 6, 7, 8], &s) == search([5, 6, 7, 8], &s));
+}
+
+#[test]
+fn different_seeds_give_different_searches() {
+    let s = Nim::new();
+    assert!(search([1, 2, 3, 4], &s) != search([5, 6, 7, 8], &s));
+}
@@ -0,0 +1,120 @@
+extern crate game_trees;
+
+use game_trees::engine::{Budget, Engine, EngineSettings};
+use game_trees::game::GameState;
+use game_trees::game::nim::Nim;
+use game_trees::play::Session;
+
+fn session() -> Session<Nim> {
+    Session::new(Nim::new(), vec!["nim".to_string()])
+}
+
+fn play(session: &mut Session<Nim>, moves: &[u32]) {
+    for &m in moves {
+        session.push(m);
+    }
+}
+
+#[test]
+fn moves_are_taken_back_and_redone() {
+    let mut session = session();
+    assert!(!session.undo());
+    assert!(!session.redo());
+    play(&mut session, &[3, 5, 2]);
+    let end = session.position();
+    assert!(session.undo());
+    assert!(session.undo());
+    assert_eq!(session.moves, vec![3]);
+    assert_eq!(session.undone, vec![2, 5]);
+    assert!(session.position() == Nim::with_total(3, true));
+    assert!(session.redo());
+    assert!(session.redo());
+    assert!(!session.redo());
+    assert!(session.position() == end);
+}
+
+#[test]
+fn replaying_the_next_move_keeps_the_rest() {
+    let mut session = session();
+    play(&mut session, &[3, 5, 2]);
+    session.undo();
+    session.undo();
+    play(&mut session, &[5]);
+    assert_eq!(session.undone, vec![2]);
+}
+
+#[test]
+fn other_moves_clear_those_taken_back() {
+    let mut session = session();
+    play(&mut session, &[3, 5, 2]);
+    session.undo();
+    session.undo();
+    play(&mut session, &[4]);
+    assert!(session.undone.is_empty());
+    assert!(!session.redo());
+    assert_eq!(session.moves, vec![3, 4]);
+}
+
+#[test]
+fn goto_moves_within_the_history() {
+    let mut session = session();
+    play(&mut session, &[3, 5, 2]);
+    let end = session.position();
+    session.goto(0).unwrap();
+    assert!(session.position() == Nim::new());
+    assert_eq!(session.undone.len(), 3);
+    session.goto(2).unwrap();
+    assert_eq!(session.moves, vec![3, 5]);
+    // Moves taken back still count
+    assert!(session.goto(4).is_err());
+    assert_eq!(session.moves.len(), 2);
+    session.goto(3).unwrap();
+    assert!(session.position() == end);
+}
+
+#[test]
+fn history_marks_moves_taken_back() {
+    let mut session = session();
+    assert!(session.history().is_empty());
+    play(&mut session, &[3, 5, 2]);
+    session.undo();
+    assert_eq!(
+        session.history(),
+        vec!["1. First player: 3", "2. Second player: 5", "3. First player: 2 (taken back)"]
+    );
+}
+
+#[test]
+fn the_engine_keeps_what_it_learnt_about_positions_taken_back() {
+    let mut session = session();
+    let engine = Engine::new(session.position(), EngineSettings::default());
+    let budget = Budget {
+        playouts: Some(500),
+        time: None,
+    };
+    engine.best_move(budget);
+    engine.stop();
+    let start = session.position();
+    let learnt = engine.playouts(&start);
+    for &m in &[3, 5] {
+        session.push(m);
+        engine.play(m);
+    }
+    engine.best_move(budget);
+    engine.stop();
+    let later = session.position();
+    let learnt_later = engine.playouts(&later);
+    // Going back to where the engine has searched before, as the play loop does
+    session.undo();
+    engine.ponder(session.position());
+    engine.stop();
+    session.goto(0).unwrap();
+    engine.ponder(session.position());
+    engine.stop();
+    assert!(engine.position() == start);
+    assert!(engine.playouts(&start) >= learnt);
+    session.goto(2).unwrap();
+    engine.ponder(session.position());
+    engine.stop();
+    assert!(engine.playouts(&later) >= learnt_later);
+}